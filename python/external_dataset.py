@@ -4,6 +4,7 @@ import zmq
 import threading
 import queue
 import pickle
+import json
 from typing import List
 
 class ExternalDataset(torch.utils.data.IterableDataset):
@@ -26,6 +27,10 @@ class ExternalDataset(torch.utils.data.IterableDataset):
         data = self.socket.recv()
         self.info = pickle.loads(data)
 
+        # Checkpoint of the last batch which was fully consumed
+        self.socket.send_string("Checkpoint")
+        self.last_checkpoint = pickle.loads(self.socket.recv())
+
         # Start the loading process
         load_thread = threading.Thread(target=self.__load_data__)
         self.data_queue = queue.Queue(maxsize = maxsize)
@@ -50,9 +55,19 @@ class ExternalDataset(torch.utils.data.IterableDataset):
                 print("Done with Download")
                 break
             result = pickle.loads(data)
-            self.data_queue.put(result)
+            self.socket.send_string("Checkpoint")
+            checkpoint = pickle.loads(self.socket.recv())
+            self.data_queue.put((result, checkpoint))
             #self.flatten(result)
 
+    def checkpoint(self):
+        return self.last_checkpoint
+
+    # Store the checkpoint which is passed to the loader with --resume
+    def save_checkpoint(self, path):
+        with open(path, 'w') as f:
+            json.dump(self.last_checkpoint, f)
+
     def __iter__(self):
         return self.__internal_item__()
 
@@ -66,7 +81,7 @@ class ExternalDataset(torch.utils.data.IterableDataset):
 
     def __internal_item__(self):
         while True:
-            data = self.data_queue.get()
+            data, checkpoint = self.data_queue.get()
             keys = list(data.keys())
             for x in range(len(data[keys[0]])):
                 result = dict()
@@ -76,6 +91,7 @@ class ExternalDataset(torch.utils.data.IterableDataset):
                     yield result
                 except:
                     pass
+            self.last_checkpoint = checkpoint
 
     def __getitem__(self, idx):
         return next(self.internal_iter)
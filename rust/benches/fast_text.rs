@@ -0,0 +1,41 @@
+use std::time::{Duration, Instant};
+
+use loader::provider::{fast_text, provider_util};
+
+
+// Compares the fast text extractor with a full json decode of each line
+// Run with cargo bench --bench fast_text
+
+fn create_lines(count:usize) -> Vec<String> {
+    (0..count).map(|i| {
+        // Lines similar to the pile with an escaped new line between the paragraphs
+        let text = format!("Paragraph of document {} with a few sentences of plain text. The second sentence is longer than the first one.\\n", i).repeat(20);
+        format!(r#"{{"id": {}, "text": "{}", "meta": {{"pile_set_name": "Pile-CC", "tags": ["a", "b"]}}}}"#, i, text)
+    }).collect()
+}
+
+fn measure<F:Fn(String) -> Option<String>>(lines:&[String], rounds:usize, f:F) -> Duration {
+    // The lines are copied outside of the timing as the filter receives an owned line from the provider
+    let mut total = Duration::ZERO;
+    for _ in 0..rounds {
+        let inputs = lines.to_vec();
+        let start = Instant::now();
+        for line in inputs {
+            std::hint::black_box(f(line));
+        }
+        total += start.elapsed();
+    }
+    total
+}
+
+fn main() {
+    let lines = create_lines(10000);
+    let rounds = 10;
+    let bytes:usize = lines.iter().map(|e| e.len()).sum::<usize>() * rounds;
+    for (name, time) in [
+        ("create_json_text", measure(&lines, rounds, |e| provider_util::create_json_text(e, "text").ok().flatten())),
+        ("fast_text", measure(&lines, rounds, |e| fast_text::get_text(e, "text").ok().flatten()))
+    ] {
+        println!("{:<20} {:>10.2?} {:>8.1} MB/s", name, time, bytes as f64 / time.as_secs_f64() / 1e6);
+    }
+}
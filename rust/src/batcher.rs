@@ -3,7 +3,7 @@ use std::sync::Arc;
 use serde::{Serialize, Deserialize};
 use tokio::{sync::mpsc::Receiver, task::{JoinHandle, self}};
 
-use crate::{provider::ProviderChannel};
+use crate::{provider::ProviderChannel, checkpoint::DataCheckpoint};
 
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -28,13 +28,37 @@ pub trait Batcher {
     type T;
     fn create_sync_batch(&mut self, data:Self::S) -> Option<Self::T>;
     fn get_working_batch(&mut self) -> Option<Self::T>;
+    // Tokens held for batches which haven't been created
+    fn get_carry(&self) -> Vec<Vec<u32>> {
+        vec![]
+    }
+    // Continue after the delivered batches of a checkpoint
+    fn resume(&mut self, _batches:u64, _carry:Vec<Vec<u32>>) {}
+}
+
+// Send the checkpoint which is valid after the batch followed by the batch
+async fn send_batch<T>(batch:T, checkpoint:&mut DataCheckpoint, carry:Vec<Vec<u32>>, tx:&tokio::sync::mpsc::Sender<ProviderChannel<T>>) {
+    checkpoint.batches += 1;
+    checkpoint.carry = carry;
+    let _ = tx.send(ProviderChannel::Checkpoint(checkpoint.clone())).await;
+    let _ = tx.send(ProviderChannel::Data(batch)).await;
 }
 
 pub async fn create_batch<S,T>(mut rx:Receiver<ProviderChannel<S>>, 
     tx_transport:tokio::sync::mpsc::Sender<ProviderChannel<T>>,
-    mut batcher:Box<dyn Batcher<S = S, T = T> + Send>
+    mut batcher:Box<dyn Batcher<S = S, T = T> + Send>,
+    resume:Option<DataCheckpoint>
     ) {
 
+    let mut checkpoint = DataCheckpoint::default();
+    // Documents to drop when the provider can't continue from the checkpoint position
+    let mut skip = 0;
+    if let Some(x) = resume {
+        log::info!("Resuming Batcher after {} Batches and {} Documents", x.batches, x.documents);
+        skip = x.get_skip();
+        batcher.resume(x.batches, x.carry.clone());
+        checkpoint = DataCheckpoint { carry: vec![], ..x };
+    }
     
     loop {
         // Wait for Data from the Transmit Link
@@ -49,12 +73,18 @@ pub async fn create_batch<S,T>(mut rx:Receiver<ProviderChannel<S>>,
                 //log::info!("Sending Dataset Info");
                 let _ = tx_transport.send(ProviderChannel::Info(x)).await;
             }
+            ProviderChannel::Position(x) => {
+                checkpoint.position = Some(x);
+            },
+            ProviderChannel::Checkpoint(_) => {},
+            // Source errors are logged by the provider
+            ProviderChannel::Error(_) => {},
             ProviderChannel::Complete => {
                 // Flush the Current Packet
                 let current = batcher.get_working_batch();
                 match current {
                     Some(x) => {
-                        let _ = tx_transport.send(ProviderChannel::Data(x)).await;
+                        send_batch(x, &mut checkpoint, vec![], &tx_transport).await;
                     },
                     None => {}
                 }
@@ -63,12 +93,20 @@ pub async fn create_batch<S,T>(mut rx:Receiver<ProviderChannel<S>>,
                 break;
             },
             ProviderChannel::Data(x) => {
+                if let Some(position) = checkpoint.position.as_mut() {
+                    position.offset += 1;
+                }
+                if skip > 0 {
+                    skip -= 1;
+                    continue;
+                }
+                checkpoint.documents += 1;
                 let batch = batcher.create_sync_batch(x);
                 if batch.is_some() {
                     // Batch");
                     //log::info!("Sending Data");
                     let real_batch = batch.unwrap();
-                    let _result = tx_transport.send(ProviderChannel::Data(real_batch)).await;
+                    send_batch(real_batch, &mut checkpoint, batcher.get_carry(), &tx_transport).await;
                 }
             },
         }
@@ -84,10 +122,83 @@ pub async fn create_batcher<P:Send + 'static, D:Serialize+Send+'static>(value:Ar
     let generator = generator(&value);
 
     let join_tokenizer = task::spawn(async move {
-        let result = create_batch(rx, tx, generator);
+        let result = create_batch(rx, tx, generator, None);
         result.await;
     });
     join_tokenizer
 }
 
 
+
+
+#[tokio::test]
+async fn test_batch_checkpoint() {
+    use crate::checkpoint::DataPosition;
+
+    // Batches of two documents
+    struct PairBatcher {
+        batch:Vec<u32>
+    }
+    impl Batcher for PairBatcher {
+        type S = u32;
+        type T = Vec<u32>;
+        fn create_sync_batch(&mut self, data:u32) -> Option<Vec<u32>> {
+            self.batch.push(data);
+            if self.batch.len() == 2 {self.get_working_batch()} else {None}
+        }
+        fn get_working_batch(&mut self) -> Option<Vec<u32>> {
+            Some(std::mem::take(&mut self.batch)).filter(|e| !e.is_empty())
+        }
+        fn get_carry(&self) -> Vec<Vec<u32>> {
+            vec![self.batch.clone()].into_iter().filter(|e| !e.is_empty()).collect()
+        }
+    }
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<u32>>(16);
+    let (tx_transport, mut rx_transport) = tokio::sync::mpsc::channel::<ProviderChannel<Vec<u32>>>(16);
+    tokio::spawn(create_batch(rx, tx_transport, Box::new(PairBatcher { batch: vec![] }), None));
+    tokio::spawn(async move {
+        let _ = tx.send(ProviderChannel::Position(DataPosition { epoch: 0, dataset: 3, offset: 0 })).await;
+        for x in 0..5 {
+            let _ = tx.send(ProviderChannel::Data(x)).await;
+        }
+        let _ = tx.send(ProviderChannel::Complete).await;
+    });
+
+    let mut checkpoints = Vec::<DataCheckpoint>::new();
+    let mut batches = Vec::<Vec<u32>>::new();
+    while let Some(data) = rx_transport.recv().await {
+        match data {
+            // Each checkpoint is sent before its batch
+            ProviderChannel::Checkpoint(x) => {assert_eq!(checkpoints.len(), batches.len()); checkpoints.push(x)},
+            ProviderChannel::Data(x) => batches.push(x),
+            _ => break
+        }
+    }
+    assert_eq!(batches, vec![vec![0, 1], vec![2, 3], vec![4]]);
+    assert_eq!(checkpoints[1].batches, 2);
+    assert_eq!(checkpoints[1].documents, 4);
+    assert_eq!(checkpoints[1].position, Some(DataPosition { epoch: 0, dataset: 3, offset: 4 }));
+    assert_eq!(checkpoints[2].documents, 5);
+
+    // Without a position the documents of the checkpoint are skipped
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<u32>>(16);
+    let (tx_transport, mut rx_transport) = tokio::sync::mpsc::channel::<ProviderChannel<Vec<u32>>>(16);
+    let resume = DataCheckpoint { position: None, documents: 2, batches: 1, carry: vec![] };
+    tokio::spawn(create_batch(rx, tx_transport, Box::new(PairBatcher { batch: vec![] }), Some(resume)));
+    tokio::spawn(async move {
+        for x in 0..5 {
+            let _ = tx.send(ProviderChannel::Data(x)).await;
+        }
+        let _ = tx.send(ProviderChannel::Complete).await;
+    });
+    let mut batches = Vec::<Vec<u32>>::new();
+    while let Some(data) = rx_transport.recv().await {
+        match data {
+            ProviderChannel::Data(x) => batches.push(x),
+            ProviderChannel::Complete => break,
+            _ => {}
+        }
+    }
+    assert_eq!(batches, vec![vec![2, 3], vec![4]]);
+}
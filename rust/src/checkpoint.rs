@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+
+// Resumable position of the data stream
+// The provider sends its position at the start of every dataset (file) or epoch and the batcher counts the
+// documents consumed after it. A checkpoint is recorded with every batch and the transport returns the checkpoint
+// of the last delivered batch. The random state doesn't need to be stored as the shuffled orders are derived from
+// the seed (0 without a seed) and the epoch which is part of the checkpoint. The masking is only repeated with a seed
+// as it is derived from the seed and the batch index
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct DataPosition {
+    pub epoch:usize,
+    pub dataset:usize, // Index of the dataset in the source list
+    pub offset:usize // Documents read from the dataset (rows in the epoch for arrow sources)
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct DataCheckpoint {
+    // Position of the next document. Not available when the order is changed after the provider (shuffle buffer
+    // or mixture) in which case the resume skips the consumed documents
+    pub position:Option<DataPosition>,
+    pub documents:usize, // Documents consumed by the delivered batches
+    pub batches:u64, // Delivered batches which is the index of the next batch seed
+    #[serde(default)]
+    pub carry:Vec<Vec<u32>> // Tokens held by the batcher which aren't part of a delivered batch
+}
+
+impl DataCheckpoint {
+    // Load a checkpoint stored as json by the trainer
+    pub fn load(path:&str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Can't Read Checkpoint {} : {}", path, e))?;
+        serde_json::from_str(&text).map_err(|e| format!("Can't Parse Checkpoint {} : {}", path, e))
+    }
+
+    // Documents which should be dropped by the batcher as the provider can't seek to them
+    pub fn get_skip(&self) -> usize {
+        if self.position.is_none() {self.documents} else {0}
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
@@ -7,7 +9,7 @@ use crate::models::bert_data::BertData;
 use crate::models::gpt_data::GptData;
 use crate::models::hier_bert_data::BertHierData;
 use crate::models::t5_data::T5Data;
-use crate::provider::provider_config::ProviderConfig;
+use crate::provider::provider_config::{ProviderConfig, SourceDescription};
 use crate::tokenizer::tokenizer_config::{TokenizerInternalConfig};
 use crate::batcher::BatchConfig;
 use crate::tokenizer::tokenizer_wrapper::{TokenizerInfo};
@@ -40,19 +42,20 @@ pub enum ModelType {
 }
 
 impl ModelType {
-    pub fn create_dataset(&self, dataset_config:DataSetConfig, batch_config:BatchConfig, tokenizer_info:TokenizerInfo) -> DataSet{
+    // The seed is the batch seed from random::get_batch_seed which is used for masking and span corruption
+    pub fn create_dataset(&self, dataset_config:DataSetConfig, batch_config:BatchConfig, tokenizer_info:TokenizerInfo, seed:Option<u64>) -> DataSet{
         match self {
             ModelType::Bert =>  {
-                BertData::new(batch_config, dataset_config).into()
+                BertData::new(batch_config, dataset_config, seed).into()
             }
             ModelType::Gpt2 =>  {
                 GptData::new(batch_config, dataset_config).into()
             }
             ModelType::T5 =>  {
-                T5Data::new(batch_config, dataset_config, tokenizer_info).into()
+                T5Data::new(batch_config, dataset_config, tokenizer_info, seed).into()
             }
             ModelType::BertHier => {
-                BertHierData::new(batch_config, dataset_config, 5).into()
+                BertHierData::new(batch_config, dataset_config, 5, seed).into()
             }
             _ => todo!()
         }
@@ -68,5 +71,132 @@ pub struct TrainingConfig {
     pub batch:BatchConfig,
     pub transport:TransportConfig,
     pub node:NodeConfig,
-    pub dataset_config:DataSetConfig
-}
\ No newline at end of file
+    pub dataset_config:DataSetConfig,
+    #[serde(default)]
+    pub seed:Option<u64> // Seed for all of the random operations which makes the batches reproducible
+}
+
+impl TrainingConfig {
+
+    // Load a complete training configuration from a yaml or json file
+    // Overrides are of the form key.path=value and are applied before the config is deserialized
+    pub fn load(path:&str, overrides:&[String]) -> Result<TrainingConfig, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| format!("Can't Read Config {} : {}", path, e))?;
+        let is_json = Path::new(path).extension().map(|e| e == "json").unwrap_or(false);
+        if is_json {
+            let mut value:serde_json::Value = serde_json::from_str(&contents).map_err(|e| format!("Can't Parse Config {} : {}", path, e))?;
+            for setting in overrides {
+                let (key, data) = split_override(setting)?;
+                let data = serde_json::from_str(data).unwrap_or_else(|_| serde_json::Value::String(data.to_string()));
+                set_json_value(&mut value, key, data)?;
+            }
+            serde_json::from_value(value).map_err(|e| format!("Invalid Config {} : {}", path, e))
+        }
+        else {
+            let mut value:serde_yaml::Value = serde_yaml::from_str(&contents).map_err(|e| format!("Can't Parse Config {} : {}", path, e))?;
+            for setting in overrides {
+                let (key, data) = split_override(setting)?;
+                let data = serde_yaml::from_str(data).unwrap_or_else(|_| serde_yaml::Value::String(data.to_string()));
+                set_yaml_value(&mut value, key, data)?;
+            }
+            serde_yaml::from_value(value).map_err(|e| format!("Invalid Config {} : {}", path, e))
+        }
+    }
+
+    // Check that the configuration can be run for the task before any of the services are started
+    pub fn validate(&self, task:&TaskType) -> Result<(), String> {
+        if self.batch.batch_size == 0 || self.batch.sequence_length == 0 {
+            return Err("Batch Size and Sequence Length must be Non Zero".to_string());
+        }
+        if let ModelType::Roberta = self.model_config {
+            return Err("Model Roberta is not Supported".to_string());
+        }
+
+        let dataset_valid = match task {
+            TaskType::Mlm => matches!(self.dataset_config, DataSetConfig::Mask{..}),
+            TaskType::Clm => matches!(self.dataset_config, DataSetConfig::Gpt),
+            TaskType::Span => matches!(self.dataset_config, DataSetConfig::Span{..}),
+            TaskType::Squad => matches!(self.dataset_config, DataSetConfig::Squad),
+            TaskType::MultiLabel => matches!(self.dataset_config, DataSetConfig::MultiLabel{..}),
+            TaskType::SingleClass => matches!(self.dataset_config, DataSetConfig::SingleClass),
+            TaskType::Python | TaskType::SpanPython => matches!(self.dataset_config, DataSetConfig::MaskHier{..} | DataSetConfig::SpanHier{..}),
+            TaskType::Context => true,
+        };
+        if !dataset_valid {
+            return Err(format!("Dataset Config {:?} not Supported for Task {:?}", self.dataset_config, task));
+        }
+
+        let source_valid = match task {
+            TaskType::Squad | TaskType::MultiLabel | TaskType::SingleClass => {
+                matches!(self.source.source, SourceDescription::HuggingFace(_) | SourceDescription::Arrow(_) | SourceDescription::Parquet(_))
+            }
+            _ => matches!(self.source.source, SourceDescription::DataList(_) | SourceDescription::Pile{..} | SourceDescription::Arrow(_) | SourceDescription::Parquet(_) | SourceDescription::Mixture(_) | SourceDescription::Stdin | SourceDescription::Fifo(_))
+        };
+        if !source_valid {
+            return Err(format!("Source {:?} not Supported for Task {:?}", self.source.source, task));
+        }
+        if let SourceDescription::Mixture(x) = &self.source.source {
+            if x.components.is_empty() || x.components.iter().any(|e| e.weight < 0.0) || x.components.iter().all(|e| e.weight == 0.0) {
+                return Err("Mixture requires Components with non-negative Weights".to_string());
+            }
+            if x.temperature.map(|e| e <= 0.0).unwrap_or(false) {
+                return Err("Mixture Temperature must be greater than 0".to_string());
+            }
+        }
+        if let Some(path) = self.source.quality.as_ref().and_then(|e| e.bad_words_file.as_ref()) {
+            if let Err(e) = std::fs::File::open(path) {
+                return Err(format!("Can't Read Bad Words {} {:?}", path, e));
+            }
+        }
+        if self.source.world_size == Some(0) || self.source.rank.unwrap_or(0) >= self.source.world_size.unwrap_or(1) {
+            return Err(format!("Rank {:?} must be less than the World Size {:?}", self.source.rank, self.source.world_size));
+        }
+        Ok(())
+    }
+}
+
+fn split_override(setting:&str) -> Result<(&str, &str), String> {
+    setting.split_once('=').ok_or(format!("Override {} must be of the form key.path=value", setting))
+}
+
+// Walks the yaml value following the key path. Tags (!iterations, !list, ...) are passed through
+fn set_yaml_value(value:&mut serde_yaml::Value, key:&str, data:serde_yaml::Value) -> Result<(), String> {
+    let mut current = value;
+    for part in key.split('.') {
+        while let serde_yaml::Value::Tagged(tagged) = current {
+            current = &mut tagged.value;
+        }
+        current = match current {
+            serde_yaml::Value::Mapping(map) => {
+                let name = serde_yaml::Value::String(part.to_string());
+                if !map.contains_key(&name) {
+                    map.insert(name.clone(), serde_yaml::Value::Null);
+                }
+                map.get_mut(&name).unwrap()
+            },
+            serde_yaml::Value::Sequence(list) => {
+                let index:usize = part.parse().map_err(|_| format!("Override {} : {} is not a list index", key, part))?;
+                list.get_mut(index).ok_or(format!("Override {} : Index {} out of range", key, index))?
+            },
+            _ => return Err(format!("Override {} : Can't find {}", key, part))
+        };
+    }
+    *current = data;
+    Ok(())
+}
+
+fn set_json_value(value:&mut serde_json::Value, key:&str, data:serde_json::Value) -> Result<(), String> {
+    let mut current = value;
+    for part in key.split('.') {
+        current = match current {
+            serde_json::Value::Object(map) => map.entry(part.to_string()).or_insert(serde_json::Value::Null),
+            serde_json::Value::Array(list) => {
+                let index:usize = part.parse().map_err(|_| format!("Override {} : {} is not a list index", key, part))?;
+                list.get_mut(index).ok_or(format!("Override {} : Index {} out of range", key, index))?
+            },
+            _ => return Err(format!("Override {} : Can't find {}", key, part))
+        };
+    }
+    *current = data;
+    Ok(())
+}
@@ -8,6 +8,8 @@ pub mod batcher;
 pub mod tokenizer;
 pub mod logger;
 pub mod config;
+pub mod random;
+pub mod checkpoint;
 
 //pub mod py_interface;
 //pub mod py_conversions;
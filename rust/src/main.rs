@@ -2,7 +2,9 @@
 
 
 use clap::{Parser, ValueEnum};
-use loader::{tasks::{cases::BasicCases, python::{python_cases, python_runner}}, config::{TaskType, ModelType}};
+use std::time::Duration;
+
+use loader::{checkpoint::DataCheckpoint, provider::{cache_manager, corpus_writer::{self, ShardWriter}}, tasks::{cases::BasicCases, masking::masking_runner, python::{python_cases, python_runner}}, config::{TaskType, ModelType, TrainingConfig}};
 
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -10,7 +12,16 @@ use loader::{tasks::{cases::BasicCases, python::{python_cases, python_runner}},
 enum Mode {
     Run,
     Filter,
-    Context
+    Context,
+    Cache
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum CacheAction {
+    List,
+    Verify,
+    Prune,
+    Prewarm
 }
  
 
@@ -34,6 +45,97 @@ struct Args {
     #[arg(short, long, action)]
     test: bool,
 
+    /// Training configuration file (yaml or json) used instead of the built in cases
+    #[arg(long, default_value=None)]
+    config: Option<String>,
+
+    /// Overrides for the configuration file of the form key.path=value
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// Checkpoint (json) from the transport used to continue the data stream
+    #[arg(long, default_value=None)]
+    resume: Option<String>,
+
+    /// Operation on the --cache directory for the cache mode
+    #[clap(long, value_enum, default_value_t=CacheAction::List)]
+    action: CacheAction,
+
+    /// Prune cache entries older than the number of days
+    #[arg(long, default_value=None)]
+    max_age_days: Option<f64>,
+
+    /// Prune the oldest cache entries until the cache is within the size
+    #[arg(long, default_value=None)]
+    max_size_gb: Option<f64>,
+
+    /// Output directory of the filtered shards for the filter mode
+    #[arg(long, default_value=None)]
+    output: Option<String>,
+
+    /// Size of the text in each filtered shard
+    #[arg(long, default_value=None)]
+    shard_size_mb: Option<f64>,
+
+}
+
+
+fn load_config(path:&str, overrides:&[String], task:&TaskType) -> TrainingConfig {
+    let config = TrainingConfig::load(path, overrides).and_then(|c| c.validate(task).map(|_| c));
+    match config {
+        Ok(x) => x,
+        Err(e) => {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn format_size(bytes:u64) -> String {
+    format!("{:.2} MB", bytes as f64 / 1e6)
+}
+
+fn format_age(age:Duration) -> String {
+    format!("{:.1} days", age.as_secs_f64() / 86400.0)
+}
+
+async fn run_cache(args:&Args, cache:&str, config:TrainingConfig) {
+    match args.action {
+        CacheAction::List => {
+            let entries = cache_manager::list_cache(cache);
+            for entry in &entries {
+                let source = entry.manifest.as_ref().map(|e| e.source.clone()).unwrap_or_else(|| "Incomplete".to_string());
+                println!("{:>12} {:>12} {} {:?}", format_size(entry.bytes), format_age(entry.get_age()), source, entry.location);
+            }
+            println!("{} Entries {}", entries.len(), format_size(entries.iter().map(|e| e.bytes).sum()));
+        },
+        CacheAction::Verify => {
+            let mut failed = 0;
+            for entry in cache_manager::list_cache(cache).iter().filter(|e| !e.temporary) {
+                if let Err(e) = cache_manager::verify_entry(entry) {
+                    println!("Failed {:?} : {}", entry.location, e);
+                    failed += 1;
+                }
+            }
+            println!("{} Entries Failed Verification", failed);
+            if failed > 0 {
+                std::process::exit(1);
+            }
+        },
+        CacheAction::Prune => {
+            let max_age = args.max_age_days.map(|e| Duration::from_secs_f64(e * 86400.0));
+            let max_size = args.max_size_gb.map(|e| (e * 1e9) as u64);
+            let removed = cache_manager::prune_cache(cache, max_age, max_size);
+            for entry in &removed {
+                println!("Removed {:?}", entry.location);
+            }
+            println!("Removed {} Entries {}", removed.len(), format_size(removed.iter().map(|e| e.bytes).sum()));
+        },
+        CacheAction::Prewarm => {
+            let count = cache_manager::prewarm(&config.source, cache).await;
+            println!("{} Sources Cached", count);
+        }
+    }
 }
 
 
@@ -57,17 +159,66 @@ async fn main()  {
     
     match args.mode {
         Mode::Run => {
-            let example = config.get_config(args.test);
+            let mut example = match &args.config {
+                Some(path) => load_config(path, &args.set, &args.task),
+                None => config.get_config(args.test)
+            };
+            if let Some(path) = &args.resume {
+                match DataCheckpoint::load(path) {
+                    Ok(x) => example.source.resume = Some(x),
+                    Err(e) => {
+                        log::error!("{}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
             let result = loader::tasks::run(example, args.task, args.cache, None).await;
             log::info!("Final Result {}", result);
         },
         Mode::Filter => {
-
+            let output = match &args.output {
+                Some(x) => x.clone(),
+                None => {
+                    log::error!("Filter Mode requires --output");
+                    std::process::exit(1);
+                }
+            };
+            let example = match &args.config {
+                Some(path) => load_config(path, &args.set, &args.task),
+                None => config.get_config(args.test)
+            };
+            let shard_size = args.shard_size_mb.map(|e| (e * 1e6) as u64).unwrap_or(corpus_writer::DEFAULT_SHARD_SIZE);
+            let source = args.config.clone().unwrap_or_else(|| format!("{:?}", args.task));
+            let writer = match ShardWriter::new(&output, &source, shard_size) {
+                Some(x) => x,
+                None => std::process::exit(1)
+            };
+            match corpus_writer::write_corpus(example.source, masking_runner::create_provider, args.cache, writer).await {
+                Some(x) => log::info!("Filtered Corpus {} with {} Shards", output, x.len()),
+                None => {
+                    log::error!("Filter Failed {}", output);
+                    std::process::exit(1);
+                }
+            }
         },
         Mode::Context => {
             let example = python_cases::get_case(python_cases::Cases::Context, false);
             let result = python_runner::run_context(example, args.cache).await;
             log::info!("Final Result {}", result);
+        },
+        Mode::Cache => {
+            let cache = match &args.cache {
+                Some(x) => x.clone(),
+                None => {
+                    log::error!("Cache Mode requires --cache");
+                    std::process::exit(1);
+                }
+            };
+            let example = match &args.config {
+                Some(path) => load_config(path, &args.set, &args.task),
+                None => config.get_config(args.test)
+            };
+            run_cache(&args, &cache, example).await;
         }
     }
     
@@ -2,7 +2,8 @@
 use serde::{Serialize, Deserialize, ser::SerializeStruct};
 
 use crate::{batcher::BatchConfig, tokenizer::tokenizer_wrapper::TokenizerWrapper, models::simple_label::{Label}, datasets::dataset_config::DataSetConfig};
-use rand::{thread_rng, seq::SliceRandom};
+use rand::seq::SliceRandom;
+use crate::random::{self, RandomStream};
 
 use core::fmt::Debug;
 
@@ -19,12 +20,14 @@ pub struct BertData {
 
     batch_config:BatchConfig,
     dataset_config:DataSetConfig,
-    index:usize
+    index:usize,
+    #[serde(skip)]
+    seed:Option<u64>
 }
 
 
 impl BertData {
-    pub fn new(batch_config:BatchConfig, dataset_config:DataSetConfig) -> Self{
+    pub fn new(batch_config:BatchConfig, dataset_config:DataSetConfig, seed:Option<u64>) -> Self{
         Self {
             input_ids: batch_config.create_vector(0),
             attention_mask: batch_config.create_vector(1),
@@ -33,13 +36,14 @@ impl BertData {
 
             dataset_config,
             batch_config,
-            index:0
+            index:0,
+            seed
         }
     } 
 
     pub fn mask_batch(&mut self, masked_length:usize, mask:u32) {
         let mut position_base:Vec<u32> = (0..self.batch_config.sequence_length as u32).collect();
-        position_base.shuffle(&mut thread_rng());
+        position_base.shuffle(&mut random::create_rng(self.seed, RandomStream::Sample, self.index as u64));
         let mut new_labels = vec![-100;self.batch_config.sequence_length];
 
         for x in 0..masked_length as usize {
@@ -142,4 +146,17 @@ impl Serialize for BertData {
             //state.serialize_field("label", &self.label)?;
             state.end()
     }
-}
\ No newline at end of file
+}
+
+#[test]
+pub fn test_seeded_mask() {
+    let create = |seed:Option<u64>| {
+        let mut data = BertData::new(BatchConfig{ batch_size: 2, sequence_length: 32 }, DataSetConfig::Mask { mask_length: 8, mask: 1 }, seed);
+        for _ in 0..2 {
+            data.put_data((10..42).collect(), None);
+        }
+        data.input_ids
+    };
+    assert_eq!(create(Some(3)), create(Some(3)));
+    assert_ne!(create(Some(3)), create(Some(4)));
+}
@@ -2,9 +2,8 @@
 
 use serde::{Serialize, Deserialize, ser::SerializeStruct};
 use rand::prelude::SliceRandom;
-use rand::thread_rng;
 
-use crate::{batcher::BatchConfig, tokenizer::tokenizer_data::TokenizedData, models::simple_label::Label, datasets::dataset_config::DataSetConfig};
+use crate::{random::{self, RandomStream}, batcher::BatchConfig, tokenizer::tokenizer_data::TokenizedData, models::simple_label::Label, datasets::dataset_config::DataSetConfig};
 use rand::prelude::*;
 use rand_distr::StandardNormal;
 
@@ -18,10 +17,12 @@ pub struct BertHierData {
 
     batch_config:BatchConfig,
     dataset_config:DataSetConfig,
+    #[serde(skip)]
+    seed:Option<u64>
 }
 
 impl BertHierData {
-    pub fn new(batch_config:BatchConfig, dataset_config:DataSetConfig, _mask:u32) -> Self{
+    pub fn new(batch_config:BatchConfig, dataset_config:DataSetConfig, _mask:u32, seed:Option<u64>) -> Self{
         let context_size = match dataset_config.clone() {
             DataSetConfig::MaskHier { mask_length:_, context_size, front:_ } => context_size,
             //DataSetConfig::SpanHier { avg_span_gap:_, avg_span_size:_, context_size, extra_ids:_ } => context_size,
@@ -38,13 +39,14 @@ impl BertHierData {
 
             batch_config,
             dataset_config:dataset_config,
+            seed
         }
     }
 
     /// Simple Masking of the Data 
     pub fn mask_batch(&mut self, masked_length:usize, mask:u32) {
         let mut position_base:Vec<u32> = (0..self.batch_config.sequence_length as u32).collect();
-        position_base.shuffle(&mut thread_rng());
+        position_base.shuffle(&mut random::create_rng(self.seed, RandomStream::Sample, self.index as u64));
         let mut new_labels = vec![-100;self.batch_config.sequence_length];
 
         for x in 0..masked_length as usize {
@@ -59,7 +61,7 @@ impl BertHierData {
     /// Masking of the Data and converted to positions and labels
     pub fn mask_batch_front(&mut self, mask_length:usize, mask:u32) {
         let mut position_base:Vec<u32> = (0..self.batch_config.sequence_length as u32).collect();
-        position_base.shuffle(&mut thread_rng());
+        position_base.shuffle(&mut random::create_rng(self.seed, RandomStream::Sample, self.index as u64));
         
         let mut masked_lm_labels = vec![-100;mask_length];
         let mut masked_lm_positions:Vec<u32> = vec![0;mask_length];
@@ -79,18 +81,19 @@ impl BertHierData {
     // Create Span Labels 
     pub fn create_span(&mut self, data:TokenizedData, avg_span_gap:f64, avg_span_size:f64, extra_ids:&Vec<u32>, context_size:usize) {
 
-        fn random_data_gap(avg_span_gap:f64) -> usize {
-            let val: f64 = thread_rng().sample(StandardNormal);
+        fn random_data_gap(avg_span_gap:f64, rng:&mut StdRng) -> usize {
+            let val: f64 = rng.sample(StandardNormal);
             let distance = avg_span_gap - val;
             distance as usize
         }
 
-        pub fn random_data_size(avg_span_size:f64) -> usize {
-            let val: f64 = thread_rng().sample(StandardNormal);
+        pub fn random_data_size(avg_span_size:f64, rng:&mut StdRng) -> usize {
+            let val: f64 = rng.sample(StandardNormal);
             let distance = avg_span_size - val;
             std::cmp::max(distance as usize,1)
         }
 
+        let mut rng = random::create_rng(self.seed, RandomStream::Sample, self.index as u64);
         let mut ip:usize = 0;
         let mut lp = 0;
         let mut ap = 0;
@@ -98,7 +101,7 @@ impl BertHierData {
 
         let mut new_labels = vec![-100;self.batch_config.sequence_length];
         while lp < self.batch_config.sequence_length {
-            let mut data_gap = random_data_gap(avg_span_gap);
+            let mut data_gap = random_data_gap(avg_span_gap, &mut rng);
             data_gap = std::cmp::min(data_gap, self.batch_config.sequence_length - lp);
             data_gap = std::cmp::min(data_gap, data.ids.len() - ip);
             //log::info!("Hh {} {} {} {} {} ", self.index, ip, lp, pass, data_gap);
@@ -111,7 +114,7 @@ impl BertHierData {
                 }
                 lp += data_gap; ip += data_gap;
             }
-            data_gap = random_data_size(avg_span_size);
+            data_gap = random_data_size(avg_span_size, &mut rng);
             data_gap = std::cmp::min(data_gap, self.batch_config.sequence_length - lp);
             data_gap = std::cmp::min(data_gap, data.ids.len() - ip);
                 //log::info!("Ha {} {} {} {} {} {}", self.index, ip, lp, ap, pass, data_gap);
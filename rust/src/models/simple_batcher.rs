@@ -1,4 +1,4 @@
-use crate::{datasets::{dataset_config::DataSetConfig, dataset::DataSet}, batcher::{BatchConfig, Batcher}, tokenizer::tokenizer_wrapper::TokenizerWrapper, config::ModelType};
+use crate::{datasets::{dataset_config::DataSetConfig, dataset::DataSet}, batcher::{BatchConfig, Batcher}, tokenizer::tokenizer_wrapper::TokenizerWrapper, config::ModelType, random};
 
 use super::{simple_transport::SimpleTransport};
 
@@ -11,19 +11,21 @@ pub struct SimpleBatcher {
     batch_config:BatchConfig,
     tokenizer:TokenizerWrapper,
 
-    batch:DataSet
+    batch:DataSet,
+    seed:Option<u64>,
+    batch_count:u64
 }
 
 impl SimpleBatcher {
-    pub fn new(model_type:ModelType, dataset_config:DataSetConfig,  batch_config:BatchConfig, tokenizer:TokenizerWrapper) -> Self {
+    pub fn new(model_type:ModelType, dataset_config:DataSetConfig,  batch_config:BatchConfig, tokenizer:TokenizerWrapper, seed:Option<u64>) -> Self {
         Self {
-            batch: model_type.create_dataset(dataset_config.clone(), batch_config.clone(), tokenizer.get_tokenizer_info()),
+            batch: model_type.create_dataset(dataset_config.clone(), batch_config.clone(), tokenizer.get_tokenizer_info(), random::get_batch_seed(seed, 0)),
             model_type,
             dataset_config,
             batch_config,
             tokenizer,
-
-            
+            seed,
+            batch_count:1
         }
     }
 }
@@ -44,12 +46,21 @@ impl Batcher for SimpleBatcher {
 
     fn get_working_batch(&mut self) -> Option<Self::T> {
         
+        let seed = random::get_batch_seed(self.seed, self.batch_count);
+        self.batch_count += 1;
         let mut old_batch = self.model_type.create_dataset(self.dataset_config.clone(), 
             self.batch_config.clone(),
-            self.tokenizer.get_tokenizer_info()); 
+            self.tokenizer.get_tokenizer_info(),
+            seed); 
         std::mem::swap(&mut self.batch, &mut old_batch);
         return Some(old_batch);
     }
 
+    // Batches are complete when they are sent so only the seed of the next batch is restored
+    fn resume(&mut self, batches:u64, _carry:Vec<Vec<u32>>) {
+        self.batch_count = batches;
+        let _ = self.get_working_batch();
+    }
+
 }
 
@@ -1,6 +1,6 @@
 
 use serde::{Serialize, Deserialize, ser::SerializeStruct};
-use crate::{batcher::BatchConfig, datasets::dataset_config::{DataSetConfig}, models::simple_label::Label, tokenizer::{tokenizer_wrapper::TokenizerInfo, tokenizer_data::TokenizedData}};
+use crate::{random::{self, RandomStream}, batcher::BatchConfig, datasets::dataset_config::{DataSetConfig}, models::simple_label::Label, tokenizer::{tokenizer_wrapper::TokenizerInfo, tokenizer_data::TokenizedData}};
 
 use rand::prelude::*;
 use rand_distr::{StandardNormal, Uniform};
@@ -19,11 +19,12 @@ pub struct T5Data {
     batch_config:BatchConfig, 
     dataset_config:DataSetConfig,
     tokenizer_info:TokenizerInfo,
-
+    #[serde(skip)]
+    seed:Option<u64>
 }
 
 impl T5Data {
-    pub fn new(batch_config:BatchConfig, dataset_config:DataSetConfig, tokenizer_info:TokenizerInfo) -> Self{
+    pub fn new(batch_config:BatchConfig, dataset_config:DataSetConfig, tokenizer_info:TokenizerInfo, seed:Option<u64>) -> Self{
         
         let (head_mask, decode_head_mask) = if let DataSetConfig::SpanHier { avg_span_prob, context_size } = dataset_config {
             let max_label_length = (2.1*avg_span_prob*batch_config.sequence_length as f64).round() as usize;
@@ -49,7 +50,7 @@ impl T5Data {
             batch_config, 
             dataset_config,
             tokenizer_info,
-
+            seed
         }
     }
 
@@ -98,12 +99,13 @@ impl T5Data {
             let mut lop = 0;
 
             let uniform = Uniform::new(0.0,1.0);
+            let mut rng = random::create_rng(self.seed, RandomStream::Sample, self.index as u64);
             for (_i,gap) in data.gaps.clone().into_iter().enumerate() {
                 // Count out the number of masked tokens 
                 // Normal Distribution Approximation Failed due to small samples (or my poor coding)
                 let mut span_length = 0;
                 for _ in 0..gap {
-                    let rv = thread_rng().sample(uniform);
+                    let rv = rng.sample(uniform);
                     if rv < avg_span_prob {
                         span_length += 1;
                     }
@@ -111,7 +113,7 @@ impl T5Data {
                 
                 // Create the starting point of the label or leave it at the end of the gap
                 let sp = if span_length > 0 {
-                    let rv = thread_rng().sample(uniform);
+                    let rv = rng.sample(uniform);
                     (rv*(gap-span_length) as f64).round() as usize
                 }
                 else {
@@ -162,15 +164,15 @@ impl T5Data {
     pub fn put_data(&mut self, ids:Vec<u32>, _label:Option<Label>) -> bool{
 
         // TODO : Add Round and Proper Std scaling
-        pub fn random_data_gap(avg_span_gap:f64) -> usize {
-            let val: f64 = thread_rng().sample(StandardNormal);
+        pub fn random_data_gap(avg_span_gap:f64, rng:&mut StdRng) -> usize {
+            let val: f64 = rng.sample(StandardNormal);
             let distance = avg_span_gap - val;
             distance as usize
         }
 
         // TODO : Add Round and Proper Std Scaling
-        pub fn random_data_size(avg_span_size:f64) -> usize {
-            let val: f64 = thread_rng().sample(StandardNormal);
+        pub fn random_data_size(avg_span_size:f64, rng:&mut StdRng) -> usize {
+            let val: f64 = rng.sample(StandardNormal);
             let distance = avg_span_size - val;
             std::cmp::max(distance as usize,1)
         }
@@ -181,6 +183,7 @@ impl T5Data {
         };
 
 
+        let mut rng = random::create_rng(self.seed, RandomStream::Sample, self.index as u64);
         let mut ip:usize = 0;
         while self.index < self.batch_config.batch_size {
             let mut lp = 0;
@@ -188,7 +191,7 @@ impl T5Data {
             let mut pass = 0;
             while lp < self.batch_config.sequence_length {
                 // Create the gap between spans
-                let mut data_gap =random_data_gap(avg_span_gap);
+                let mut data_gap =random_data_gap(avg_span_gap, &mut rng);
                 data_gap = std::cmp::min(data_gap, self.batch_config.sequence_length - lp);
                 data_gap = std::cmp::min(data_gap, ids.len() - ip);
                 if data_gap > 0 {
@@ -196,7 +199,7 @@ impl T5Data {
                     lp += data_gap; ip += data_gap;
                 }
                 // Create the Span of Masked Values
-                data_gap = random_data_size(avg_span_size);
+                data_gap = random_data_size(avg_span_size, &mut rng);
                 data_gap = std::cmp::min(data_gap, self.batch_config.sequence_length - lp);
                 data_gap = std::cmp::min(data_gap, ids.len() - ip);
 
@@ -7,22 +7,34 @@ use serde::Deserialize;
 use tempfile::NamedTempFile;
 use std::io::Read;
 
+use super::hugging_cache;
+
 
 
 #[derive(Deserialize, Debug)]
 
-struct ArrowDescriptor {
+pub struct ArrowDescriptor {
     pub name:String,
     pub paths:Vec<String>,
     pub num_rows:u32
 }
 
+impl ArrowDescriptor {
+    pub fn new(name:String, paths:Vec<String>, num_rows:u32) -> Self {
+        Self { name, paths, num_rows }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ArrowFiles {
     children:Vec<ArrowDescriptor>
 }
 
 impl ArrowFiles {
+    pub fn new(children:Vec<ArrowDescriptor>) -> Self {
+        Self { children }
+    }
+
     pub fn get_locations(self, key:String) -> Option<(Vec<String>,u32)> {
         for child in self.children {
             if child.name == key {
@@ -33,11 +45,16 @@ impl ArrowFiles {
     }
 }
 
+// Directory of the python scripts set with DATA_LOADER_PYTHON or relative to the working directory
+pub fn get_python_dir() -> String {
+    std::env::var("DATA_LOADER_PYTHON").unwrap_or_else(|_| "../python".to_string())
+}
+
 // Download a Huggingface dataset
 // Uses python to download the dataset and create a pickle file with information
 // Loads the pickle file to get arrow file location and number of rows in arrow file
 pub fn download_huggingface_dataset(dataset:String, typ:Option<String>) -> Option<ArrowFiles> {
-    let cwd =  "../python";
+    let cwd = get_python_dir();
     let command = "python3";
     
     let file = NamedTempFile::new().unwrap().into_temp_path().as_os_str().to_str().unwrap().to_string();
@@ -84,11 +101,22 @@ pub fn download_huggingface_dataset(dataset:String, typ:Option<String>) -> Optio
 
 }
 
-pub fn create_hugging_description(dataset:String, extra:Option<String>, operation:String) -> (String, u32) {
-    let arrow_files = download_huggingface_dataset(dataset, extra).unwrap();
+// Use the local HuggingFace cache if the dataset has already been downloaded otherwise download with python
+pub fn get_huggingface_dataset(dataset:String, extra:Option<String>) -> Option<ArrowFiles> {
+    match hugging_cache::find_huggingface_dataset(&dataset, extra.as_deref()) {
+        Some(x) => Some(x),
+        None => {
+            log::info!("Dataset {} not in HuggingFace Cache {:?} : Downloading", dataset, hugging_cache::get_cache_root());
+            download_huggingface_dataset(dataset, extra)
+        }
+    }
+}
+
+// Returns all of the arrow shards for the operation and the total number of rows
+pub fn create_hugging_description(dataset:String, extra:Option<String>, operation:String) -> (Vec<String>, u32) {
+    let arrow_files = get_huggingface_dataset(dataset, extra).unwrap();
     let arrow_train = arrow_files.get_locations(operation).unwrap();
-    let arrow_location = arrow_train.0[0].to_owned();
-    let arrow_length = arrow_train.1;
-    (arrow_location, arrow_length)
+    log::info!("Found {} Arrow Shards with {} Rows", arrow_train.0.len(), arrow_train.1);
+    arrow_train
 }
 
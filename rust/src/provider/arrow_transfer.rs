@@ -1,73 +1,361 @@
-use arrow::{ipc::{reader::StreamReader}, datatypes::Schema};
-use rand::{seq::SliceRandom, thread_rng};
+use arrow::{ipc::{self, reader::{StreamReader, FileReader}}, datatypes::Schema, record_batch::{RecordBatch, RecordBatchReader}, error::Result as ArrowResult};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReader;
+use rand::{seq::SliceRandom, rngs::StdRng};
 use tokio::sync::mpsc::Sender;
-use std::{fs::File, sync::Arc};
+use std::{fs::File, sync::Arc, io::{Read, BufReader, Seek, SeekFrom}};
 
-use crate::tasks::DatasetInfo;
+use crate::{tasks::DatasetInfo, random::{self, RandomStream}, checkpoint::{DataCheckpoint, DataPosition}};
 
-use super::{ProviderChannel, provider_config::ProviderConfig};
+use super::{ProviderChannel, shard::Shard, provider_config::{ProviderConfig, SourceDescription}, arrow_provider, parquet_provider, provider_util};
 
 
 // Trait to support generic Loading of an Arror File into data Type 
+// Rows which can't be converted return None and are skipped
 
 pub trait ArrowGenerator {
     type T;
-    fn get_data(&self, batch:&arrow::record_batch::RecordBatch) -> Self::T;
+    fn get_data(&self, batch:&arrow::record_batch::RecordBatch) -> Option<Self::T>;
 }
 
 // Top Level Structure To Handle Loading the Arrow File
+// The dataset can be split over multiple arrow files which share the same schema
 pub struct ArrowTransfer<T:Clone> {
-    location:String,
+    locations:Vec<String>,
     pub schema:Arc<Schema>,
     pub generator:Option<Box<dyn ArrowGenerator<T=T> + Send>>,
-    pub num_rows:u32
+    pub num_rows:u32,
+    random_access:bool,
+    seed:Option<u64>, // Seed from the provider config used for the shuffle of each epoch
+    resume:Option<DataCheckpoint>, // Checkpoint from the provider config
+    skip:usize, // Rows of the epoch to skip when resuming
+    shard:Option<Shard>, // Rows of the epoch order used by the rank
+    row:usize // Position in the epoch order
 }
 
-fn create_reader(location:String) -> StreamReader<File> {
-    let f = File::open(location.clone());
-    let stream_reader = StreamReader::try_new(f.unwrap(), None).unwrap();
-    stream_reader
+// Arrow data is stored either in the streaming format (used by HuggingFace), the random access file format
+// or as parquet which is decoded to arrow
+pub enum ArrowReader {
+    Stream(StreamReader<File>),
+    File(FileReader<File>),
+    Parquet(ParquetRecordBatchReader)
+}
+
+impl ArrowReader {
+    pub fn schema(&self) -> Arc<Schema> {
+        match self {
+            ArrowReader::Stream(x) => x.schema(),
+            ArrowReader::File(x) => x.schema(),
+            ArrowReader::Parquet(x) => x.schema(),
+        }
+    }
+}
+
+impl Iterator for ArrowReader {
+    type Item = ArrowResult<RecordBatch>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ArrowReader::Stream(x) => x.next(),
+            ArrowReader::File(x) => x.next(),
+            ArrowReader::Parquet(x) => x.next(),
+        }
+    }
+}
+
+// Random access to blocks of the data which are record batches for arrow files and row groups for parquet
+enum BlockReader {
+    File(Box<FileReader<File>>),
+    Parquet(parquet_provider::ParquetFile)
+}
+
+impl BlockReader {
+    fn new(location:&str) -> Option<Self> {
+        if parquet_provider::is_parquet(location) {
+            parquet_provider::ParquetFile::open(location).map(BlockReader::Parquet)
+        }
+        else {
+            let f = File::open(location).ok()?;
+            FileReader::try_new(f, None).ok().map(|e| BlockReader::File(Box::new(e)))
+        }
+    }
+
+    fn num_blocks(&self) -> usize {
+        match self {
+            BlockReader::File(x) => x.num_batches(),
+            BlockReader::Parquet(x) => x.num_row_groups(),
+        }
+    }
+
+    async fn read_block(&mut self, index:usize) -> Vec<RecordBatch> {
+        match self {
+            BlockReader::File(x) => {
+                let _ = x.set_index(index);
+                x.next().into_iter().flatten().collect()
+            },
+            BlockReader::Parquet(x) => x.read_row_group(index).await,
+        }
+    }
+}
+
+// The file format starts with the magic bytes ARROW1 while the stream format starts with a message
+pub fn is_file_format(location:&str) -> bool {
+    let mut magic = [0u8;6];
+    let result = File::open(location).and_then(|mut f| f.read_exact(&mut magic));
+    result.is_ok() && &magic == b"ARROW1"
+}
+
+pub fn open_reader(location:&str) -> Option<ArrowReader> {
+    if parquet_provider::is_parquet(location) {
+        return parquet_provider::create_parquet_reader(location).map(|e| ArrowReader::Parquet(e.1));
+    }
+    let f = File::open(location).ok()?;
+    let reader = if is_file_format(location) {
+        FileReader::try_new(f, None).map(ArrowReader::File)
+    }
+    else {
+        StreamReader::try_new(f, None).map(ArrowReader::Stream)
+    };
+    match reader {
+        Ok(x) => Some(x),
+        Err(e) => {
+            log::error!("Can't Read Arrow File {} {:?}", location, e);
+            None
+        }
+    }
+}
+
+// Check that the files have the same columns with the same types
+fn same_columns(schema:&Schema, other:&Schema) -> bool {
+    let columns = |e:&Schema| e.fields().iter().map(|f| (f.name().clone(), f.data_type().clone())).collect::<Vec<_>>();
+    columns(schema) == columns(other)
+}
+
+// Reads the metadata of the next ipc message returning the rows of a record batch and the length of the body
+// Returns None at the end of the stream
+fn read_message<R:Read>(reader:&mut R) -> Option<(u32, i64)> {
+    let mut prefix = [0u8;4];
+    reader.read_exact(&mut prefix).ok()?;
+    // The length follows the continuation marker except in the legacy format
+    if prefix == [0xff;4] {
+        reader.read_exact(&mut prefix).ok()?;
+    }
+    let length = i32::from_le_bytes(prefix);
+    if length <= 0 {
+        return None;
+    }
+    let mut metadata = vec![0u8;length as usize];
+    reader.read_exact(&mut metadata).ok()?;
+    let message = ipc::root_as_message(&metadata).ok()?;
+    let rows = message.header_as_record_batch().map(|e| e.length() as u32).unwrap_or(0);
+    Some((rows, message.bodyLength()))
+}
+
+// Offsets of the record batches listed in the footer of the file format
+fn file_blocks(reader:&mut BufReader<File>) -> Option<Vec<u64>> {
+    let mut trailer = [0u8;10];
+    reader.seek(SeekFrom::End(-10)).ok()?;
+    reader.read_exact(&mut trailer).ok()?;
+    let length = i32::from_le_bytes(trailer[..4].try_into().ok()?);
+    reader.seek(SeekFrom::End(-10 - length as i64)).ok()?;
+    let mut footer = vec![0u8;length as usize];
+    reader.read_exact(&mut footer).ok()?;
+    let footer = ipc::root_as_footer(&footer).ok()?;
+    Some(footer.recordBatches()?.iter().map(|e| e.offset() as u64).collect())
+}
+
+// Rows of an arrow file from the record batch headers without decoding the data. The batches of the file format
+// are found from the footer and the batches of the stream format by skipping the message bodies
+fn count_arrow_rows(location:&str) -> u32 {
+    let mut reader = match File::open(location) {
+        Ok(x) => BufReader::new(x),
+        Err(e) => {
+            log::error!("Can't Read Arrow File {} {:?}", location, e);
+            return 0;
+        }
+    };
+    if is_file_format(location) {
+        let blocks = file_blocks(&mut reader).unwrap_or_default();
+        return blocks.iter().filter_map(|e| {
+            reader.seek(SeekFrom::Start(*e)).ok()?;
+            read_message(&mut reader).map(|m| m.0)
+        }).sum();
+    }
+    let mut rows = 0;
+    while let Some((batch_rows, body)) = read_message(&mut reader) {
+        rows += batch_rows;
+        if reader.seek_relative(body).is_err() {
+            break;
+        }
+    }
+    rows
+}
+
+// Count the number of rows in a set of arrow files from the file metadata
+pub fn count_rows(locations:&[String]) -> u32 {
+    locations.iter().map(|e| if parquet_provider::is_parquet(e) {parquet_provider::get_parquet_rows(e)} else {count_arrow_rows(e)}).sum()
 }
 
 impl <T:Clone>ArrowTransfer<T> {
     // Load the Arrow File and parse the schema block
-    pub fn new(location:String, length:u32) -> Self {
-        //println!("Loading Arrow File {}", location);
-        
-        let stream_reader = create_reader(location.clone());
-        let schema = stream_reader.schema(); 
+    pub fn new(location:String, length:u32) -> Option<Self> {
+        Self::new_shards(vec![location], length)
+    }
+
+    // Load a local file, directory or glob of files with the extension (.arrow or .parquet)
+    pub fn from_local(location:&str, extension:&str) -> Option<Self> {
+        let locations = provider_util::expand_local_path(location, Some(extension));
+        if locations.is_empty() {
+            log::error!("No {} Files Found at {}", extension, location);
+            return None;
+        }
+        let num_rows = count_rows(&locations);
+        log::info!("Loading {} Files with {} Rows", locations.len(), num_rows);
+        Self::new_shards(locations, num_rows)
+    }
+
+    // Load the HuggingFace, Arrow or Parquet source of the configuration
+    pub fn from_config(config:&ProviderConfig) -> Option<Self> {
+        match &config.source {
+            SourceDescription::HuggingFace(x) => {
+                let (locations, rows) = arrow_provider::create_hugging_description(x.dataset.clone(), x.args.clone(), x.get_split());
+                let loader = Self::new_shards(locations, rows);
+                if loader.is_none() {
+                    log::error!("Can't Load HuggingFace Dataset {}", x.dataset);
+                }
+                loader
+            },
+            // Single file, directory of shards or glob pattern
+            SourceDescription::Arrow(location) => Self::from_local(location, ".arrow"),
+            SourceDescription::Parquet(location) => Self::from_local(location, ".parquet"),
+            _ => {
+                log::error!("Source Not Supported for Arrow Data");
+                None
+            }
+        }
+    }
+
+    // Load a set of Arrow Files. The schema is taken from the first file which must be readable
+    pub fn new_shards(locations:Vec<String>, length:u32) -> Option<Self> {
+        let first = match locations.first() {
+            Some(x) => x,
+            None => {
+                log::error!("No Arrow Files");
+                return None;
+            }
+        };
+        let schema = open_reader(first)?.schema();
+        // The column types are only checked against the schema of the first file so files with other types are skipped
+        let (locations, skipped):(Vec<String>, Vec<String>) = locations.iter().cloned()
+            .partition(|e| open_reader(e).map(|r| same_columns(&r.schema(), &schema)).unwrap_or(true));
+        for location in &skipped {
+            log::error!("Skipping {} with a Schema which doesn't match {:?}", location, schema);
+        }
+        let length = length.saturating_sub(count_rows(&skipped));
+        // Random access to the data blocks is only possible if all of the files are in the file format or parquet
+        let random_access = locations.iter().all(|e| is_file_format(e) || parquet_provider::is_parquet(e));
         
-        Self {
-            location:location,
-            schema:schema,
+        Some(Self {
+            locations,
+            schema,
             generator:None,
-            num_rows:length
+            num_rows:length,
+            random_access,
+            seed:None,
+            resume:None,
+            skip:0,
+            shard:None,
+            row:0
+        })
+    }
+
+    // Iteration and epoch counts at the start of the data which are taken from the checkpoint when resuming
+    fn start_counts(&mut self) -> (usize, usize) {
+        match self.resume.take().and_then(|e| e.position.map(|p| (e.documents, p))) {
+            Some((documents, position)) => {
+                log::info!("Resuming Arrow Data at {:?}", position);
+                self.skip = position.offset;
+                (documents, position.epoch)
+            },
+            None => (0, 0)
+        }
+    }
+
+    async fn send_position(tx:&Sender<ProviderChannel<T>>, epoch:usize, offset:usize) {
+        let _ = tx.send(ProviderChannel::Position(DataPosition { epoch, dataset: 0, offset })).await;
+    }
+
+    // Check if the next row of the epoch belongs to the rank
+    fn next_row(&mut self, rows:usize) -> bool {
+        self.row += 1;
+        self.shard.map(|e| e.contains_row(self.row - 1, rows)).unwrap_or(true)
+    }
+
+    // Send the rows of a batch to the batcher. Returns true when the number of iterations is complete
+    async fn send_batch(&mut self, batch:&RecordBatch, tx:&Sender<ProviderChannel<T>>, shuffle:bool, rng:&mut StdRng, iterations:Option<usize>, iteration_count:&mut usize) -> bool {
+        let mut positions:Vec<usize> = (0..batch.num_rows()).collect();
+        if shuffle {
+            positions.shuffle(rng);
+        }
+        for position in positions {
+            if !self.next_row(self.num_rows as usize) {
+                continue;
+            }
+            let data = batch.slice(position, 1);
+            let result_data = match self.generator.as_ref().unwrap().get_data(&data) {
+                Some(x) => x,
+                None => continue
+            };
+            // Only rows accepted by the generator are counted in the checkpoint
+            if self.skip > 0 {
+                self.skip -= 1;
+                continue;
+            }
+            let _ = tx.send(ProviderChannel::Data(result_data)).await;
+            *iteration_count += 1;
+            if iterations.is_some() && iterations.unwrap() == *iteration_count {
+                return true;
+            }
         }
+        false
     }
 
     pub async fn load_flat_data(&mut self, capacity:usize, tx:&Sender<ProviderChannel<T>>, iterations:Option<usize>, epochs:Option<usize>, shuffle:bool) {
-        let stream = create_reader(self.location.clone()); //mem::take(&mut self.stream).unwrap();
-        
         let mut data_storage = Vec::<T>::with_capacity(capacity);
 
-        // Download and Store the Full Set of Data from the Arrow File
-        for batch_wrap in stream {
-            let batch = batch_wrap.unwrap();
-            for x in 0..batch.num_rows() {
-                let data = batch.slice(x, 1);
-                let result_data = self.generator.as_ref().unwrap().get_data(&data);
-                data_storage.push(result_data);
+        // Download and Store the Full Set of Data from the Arrow Files
+        // Shuffling the positions over the full set of data also covers the order of the shards
+        for location in &self.locations {
+            let stream = match open_reader(location) {
+                Some(x) => x,
+                None => continue
+            };
+            for batch_wrap in stream {
+                let batch = batch_wrap.unwrap();
+                for x in 0..batch.num_rows() {
+                    let data = batch.slice(x, 1);
+                    if let Some(result_data) = self.generator.as_ref().unwrap().get_data(&data) {
+                        data_storage.push(result_data);
+                    }
+                }
             }
         }
-        let mut iteration_count = 0;
-        let mut epoch_count = 0;
-        let mut positions:Vec<usize> = (0..capacity).collect();
+        let (mut iteration_count, mut epoch_count) = self.start_counts();
 
         loop {
+            // The order of each epoch starts from the stored order so it only depends on the epoch for a resume
+            let mut positions:Vec<usize> = (0..data_storage.len()).collect();
             if shuffle {
-                positions.shuffle(&mut thread_rng());
+                positions.shuffle(&mut random::create_rng(self.seed, RandomStream::Shuffle, epoch_count as u64));
             }
-            for x in 0..capacity {
+            Self::send_position(tx, epoch_count, self.skip).await;
+            self.row = 0;
+            for x in 0..positions.len() {
+                if !self.next_row(positions.len()) {
+                    continue;
+                }
+                if self.skip > 0 {
+                    self.skip -= 1;
+                    continue;
+                }
                 let data = data_storage[positions[x]].clone();
                 let _ = tx.send(ProviderChannel::Data(data)).await;
                 iteration_count += 1;
@@ -83,27 +371,43 @@ impl <T:Clone>ArrowTransfer<T> {
     }
 
     pub async fn load_stream_data(&mut self, tx:&Sender<ProviderChannel<T>>, iterations:Option<usize>, epochs:Option<usize>, shuffle:bool) {
-        let mut iteration_count = 0;
-        let mut epoch_count = 0;
+        let (mut iteration_count, mut epoch_count) = self.start_counts();
 
         loop {
-            let stream = create_reader(self.location.clone()); 
-        
-
-            for batch_wrap in stream {
-                let batch = batch_wrap.unwrap();
-                let mut positions:Vec<usize> = (0..batch.num_rows()).collect();
+            let mut rng = random::create_rng(self.seed, RandomStream::Shuffle, epoch_count as u64);
+            Self::send_position(tx, epoch_count, self.skip).await;
+            self.row = 0;
+            if shuffle && self.random_access {
+                // Shuffle the data blocks over all of the files which only requires a single block in memory
+                let mut readers:Vec<BlockReader> = self.locations.iter().filter_map(|e| BlockReader::new(e)).collect();
+                let mut blocks:Vec<(usize,usize)> = readers.iter().enumerate()
+                    .flat_map(|(shard, reader)| (0..reader.num_blocks()).map(move |index| (shard, index)))
+                    .collect();
+                blocks.shuffle(&mut rng);
+                for (shard, index) in blocks {
+                    for batch in readers[shard].read_block(index).await {
+                        if self.send_batch(&batch, tx, shuffle, &mut rng, iterations, &mut iteration_count).await {
+                            return;
+                        }
+                    }
+                }
+            }
+            else {
+                // Change the order of the shards every epoch. The order only depends on the epoch so it can be resumed
+                let mut locations = self.locations.clone();
                 if shuffle {
-                    positions.shuffle(&mut thread_rng());
+                    locations.shuffle(&mut rng);
                 }
-                for x in 0..batch.num_rows() {
-                    let data = batch.slice(positions[x], 1);
-                   
-                    let result_data = self.generator.as_ref().unwrap().get_data(&data);
-                    let _ = tx.send(ProviderChannel::Data(result_data)).await;
-                    iteration_count += 1;
-                    if iterations.is_some() && iterations.unwrap() == iteration_count {
-                        return;
+                for location in &locations {
+                    let stream = match open_reader(location) {
+                        Some(x) => x,
+                        None => continue
+                    };
+                    for batch_wrap in stream {
+                        let batch = batch_wrap.unwrap();
+                        if self.send_batch(&batch, tx, shuffle, &mut rng, iterations, &mut iteration_count).await {
+                            return;
+                        }
                     }
                 }
             }
@@ -117,6 +421,8 @@ impl <T:Clone>ArrowTransfer<T> {
     }
 
     // Async Load of the Data. Runs through all of the data contained in the arrow file
+    // Without flatten the data is streamed from the files. Arrow files in the file format support
+    // shuffling over all of the record batches without loading the dataset into memory
     // TODO : Support Epochs and longer simulation times
     pub async fn load_data(&mut self, config:ProviderConfig, tx:Sender<ProviderChannel<T>>) {
 
@@ -135,8 +441,18 @@ impl <T:Clone>ArrowTransfer<T> {
             None => true
         };
 
+        // Shuffled data uses a seed of 0 without a seed so the order is the same on every rank and for a resume
+        self.seed = if shuffle {Some(config.seed.unwrap_or(0))} else {config.seed};
+        self.shard = config.get_shard();
+        self.resume = config.resume;
+        let mut length = self.num_rows;
+        if let Some(shard) = self.shard {
+            length = shard.get_rows(self.num_rows as usize) as u32;
+            log::info!("Data Shard {:?} with {} Rows", shard, length);
+        }
+
         // Send the Information about the dataset through the channel
-        let _ = tx.send(ProviderChannel::Info(DatasetInfo{ name: "dataset".to_string(), length: self.num_rows })).await;
+        let _ = tx.send(ProviderChannel::Info(DatasetInfo{ name: "dataset".to_string(), length })).await;
 
         if flatten {
             self.load_flat_data(self.num_rows as usize, &tx, it, ep, shuffle).await;
@@ -158,3 +474,228 @@ impl <T:Clone>ArrowTransfer<T> {
 
 
 
+// Generator for the tests which returns the value of the first column of the row
+#[cfg(test)]
+struct RowGenerator {}
+
+#[cfg(test)]
+impl ArrowGenerator for RowGenerator {
+    type T = i64;
+    fn get_data(&self, batch:&RecordBatch) -> Option<i64> {
+        Some(batch.column(0).as_any().downcast_ref::<arrow::array::Int64Array>().unwrap().value(0))
+    }
+}
+
+#[tokio::test]
+async fn test_file_format_shuffle() {
+    use crate::provider::{test_util, provider_config::{ProviderLength, SourceDescription}};
+
+    // Two files in the file format with 4 batches of 5 rows each
+    let dir = tempfile::tempdir().unwrap();
+    let locations:Vec<String> = (0..2).map(|shard| {
+        let batches:Vec<Vec<i64>> = (0..4).map(|batch| (0..5).map(|e| shard*20 + batch*5 + e).collect()).collect();
+        test_util::write_arrow(&dir.path().join(format!("data-{}.arrow", shard)), &batches, false)
+    }).collect();
+    assert!(is_file_format(&locations[0]));
+    assert_eq!(count_rows(&locations), 40);
+
+    let mut loader = ArrowTransfer::<i64>::new_shards(locations.clone(), count_rows(&locations)).unwrap();
+    loader.generator = Some(Box::new(RowGenerator{}));
+    let config = ProviderConfig { shuffle: Some(true), flatten: Some(false), seed: Some(3),
+        ..ProviderConfig::new(ProviderLength::Epochs { epochs: 2 }, SourceDescription::Arrow(dir.path().to_string_lossy().to_string())) };
+
+    let collected = test_util::run_provider(|tx| async move {loader.load_data(config, tx).await}).await;
+    assert_eq!(collected.info[0].length, 40);
+    let values = collected.data;
+    assert_eq!(values.len(), 80);
+    let epochs:Vec<&[i64]> = values.chunks(40).collect();
+    for epoch in &epochs {
+        // Every row is read once in an order which differs from the order of the files
+        assert_ne!(epoch.to_vec(), (0..40).collect::<Vec<i64>>());
+        let mut sorted = epoch.to_vec();
+        sorted.sort();
+        assert_eq!(sorted, (0..40).collect::<Vec<i64>>());
+    }
+    // The record batches are shuffled again for the next epoch
+    assert_ne!(epochs[0], epochs[1]);
+}
+
+#[tokio::test]
+async fn test_row_shards() {
+    use crate::provider::{test_util, provider_config::{ProviderLength, SourceDescription}};
+
+    // 3 batches of 7 rows split between 2 ranks
+    let dir = tempfile::tempdir().unwrap();
+    let batches:Vec<Vec<i64>> = (0..3).map(|batch| (0..7).map(|e| batch*7 + e).collect()).collect();
+    let location = test_util::write_arrow(&dir.path().join("data.arrow"), &batches, false);
+
+    let mut shards = Vec::<Vec<i64>>::new();
+    for (rank, flatten) in [(0, false), (1, false), (0, true), (1, true)] {
+        let mut loader = ArrowTransfer::<i64>::new(location.clone(), 21).unwrap();
+        loader.generator = Some(Box::new(RowGenerator{}));
+        let config = ProviderConfig { shuffle: Some(true), flatten: Some(flatten), rank: Some(rank), world_size: Some(2),
+            ..ProviderConfig::new(ProviderLength::Epochs { epochs: 2 }, SourceDescription::Arrow(location.clone())) };
+
+        let collected = test_util::run_provider(|tx| async move {loader.load_data(config, tx).await}).await;
+        assert_eq!(collected.info[0].length, 10);
+        shards.push(collected.data);
+    }
+    for pair in shards.chunks(2) {
+        // Each epoch has 10 rows per rank which don't overlap between the ranks
+        assert_eq!(pair[0].len(), 20);
+        for epoch in 0..2 {
+            let first:Vec<&i64> = pair[0][epoch*10..(epoch+1)*10].iter().collect();
+            assert!(pair[1][epoch*10..(epoch+1)*10].iter().all(|e| !first.contains(&e)));
+        }
+    }
+}
+
+#[test]
+pub fn test_schema_shards() {
+    use arrow::{array::{Int64Array, StringArray}, datatypes::{Field, DataType}, ipc::writer::FileWriter};
+
+    // The second file has a text column with the same name which is skipped along with its rows
+    let dir = tempfile::tempdir().unwrap();
+    let mut locations = Vec::<String>::new();
+    let columns:[(DataType, Arc<dyn arrow::array::Array>);2] = [
+        (DataType::Int64, Arc::new(Int64Array::from(vec![1, 2, 3]))),
+        (DataType::Utf8, Arc::new(StringArray::from(vec!["a", "b"])))
+    ];
+    for (index, (typ, values)) in columns.into_iter().enumerate() {
+        let schema = Arc::new(Schema::new(vec![Field::new("value", typ, false)]));
+        let location = dir.path().join(format!("data-{}.arrow", index)).to_string_lossy().to_string();
+        let mut writer = FileWriter::try_new(File::create(&location).unwrap(), &schema).unwrap();
+        writer.write(&RecordBatch::try_new(schema, vec![values]).unwrap()).unwrap();
+        writer.finish().unwrap();
+        locations.push(location);
+    }
+    let loader = ArrowTransfer::<i64>::new_shards(locations.clone(), count_rows(&locations)).unwrap();
+    assert_eq!(loader.locations, locations[..1].to_vec());
+    assert_eq!(loader.num_rows, 3);
+}
+
+#[test]
+pub fn test_count_rows() {
+    use crate::provider::test_util;
+
+    // Batches of different sizes in both formats are counted from the headers
+    let dir = tempfile::tempdir().unwrap();
+    let batches:Vec<Vec<i64>> = [3, 7, 1].iter().map(|e| (0..*e).collect()).collect();
+    let file = test_util::write_arrow(&dir.path().join("file.arrow"), &batches, false);
+    let stream = test_util::write_arrow(&dir.path().join("stream.arrow"), &batches, true);
+
+    assert_eq!(count_rows(&[file.clone()]), 11);
+    assert_eq!(count_rows(&[stream.clone()]), 11);
+    assert_eq!(count_rows(&[file, stream, dir.path().join("missing.arrow").to_string_lossy().to_string()]), 22);
+}
+
+#[test]
+pub fn test_missing_shards() {
+    assert!(ArrowTransfer::<i64>::new_shards(vec![], 0).is_none());
+    assert!(ArrowTransfer::<i64>::new_shards(vec!["/missing/data.arrow".to_string()], 0).is_none());
+}
+
+#[tokio::test]
+async fn test_stream_shards() {
+    use crate::provider::{test_util, arrow_provider, provider_config::{ProviderLength, SourceDescription}};
+
+    // A dataset saved by HuggingFace with 4 shards in the stream format of 5 rows each
+    let dir = tempfile::tempdir().unwrap();
+    let mut files = Vec::<String>::new();
+    for shard in 0..4 {
+        let name = format!("data-{:05}-of-00004.arrow", shard);
+        test_util::write_arrow(&dir.path().join(&name), &[(0..5).map(|e| shard*10 + e).collect()], true);
+        files.push(format!("{{\"filename\": \"{}\"}}", name));
+    }
+    std::fs::write(dir.path().join("state.json"), format!("{{\"_data_files\": [{}], \"_split\": \"train\"}}", files.join(","))).unwrap();
+
+    let (locations, rows) = arrow_provider::create_hugging_description(dir.path().to_string_lossy().to_string(), None, "train".to_string());
+    assert_eq!((locations.len(), rows), (4, 20));
+
+    let mut loader = ArrowTransfer::<i64>::new_shards(locations, rows).unwrap();
+    loader.generator = Some(Box::new(RowGenerator{}));
+    let config = ProviderConfig { shuffle: Some(true), flatten: Some(false),
+        ..ProviderConfig::new(ProviderLength::Epochs { epochs: 5 }, SourceDescription::Arrow(dir.path().to_string_lossy().to_string())) };
+
+    let collected = test_util::run_provider(|tx| async move {loader.load_data(config, tx).await}).await;
+    assert_eq!(collected.info[0].length, 20);
+    let values = collected.data;
+    assert_eq!(values.len(), 100);
+    let mut orders = Vec::<Vec<i64>>::new();
+    for epoch in values.chunks(20) {
+        // Every row of every shard is read once with the rows of a shard kept together
+        let mut sorted = epoch.to_vec();
+        sorted.sort();
+        assert_eq!(sorted, (0..4).flat_map(|e| (0..5).map(move |x| e*10 + x)).collect::<Vec<i64>>());
+        let mut order:Vec<i64> = epoch.iter().map(|e| e / 10).collect();
+        order.dedup();
+        assert_eq!(order.len(), 4);
+        orders.push(order);
+    }
+    // The order of the shards changes between the epochs
+    assert!(orders.iter().any(|e| *e != orders[0]));
+}
+
+#[tokio::test]
+async fn test_unseeded_resume() {
+    use crate::provider::{test_util, provider_config::{ProviderLength, SourceDescription}};
+
+    // A file in the file format with 4 batches of 5 rows
+    let dir = tempfile::tempdir().unwrap();
+    let batches:Vec<Vec<i64>> = (0..4).map(|batch| (0..5).map(|e| batch*5 + e).collect()).collect();
+    let location = test_util::write_arrow(&dir.path().join("data.arrow"), &batches, false);
+
+    // Shuffled without a seed and resumed after 7 rows of the second epoch
+    let resume = DataCheckpoint { position: Some(DataPosition { epoch: 1, dataset: 0, offset: 7 }), documents: 27, ..Default::default() };
+    let mut runs = Vec::<Vec<i64>>::new();
+    for (flatten, resume) in [(false, None), (false, Some(resume.clone())), (true, None), (true, Some(resume))] {
+        let mut loader = ArrowTransfer::<i64>::new(location.clone(), 20).unwrap();
+        loader.generator = Some(Box::new(RowGenerator{}));
+        let config = ProviderConfig { shuffle: Some(true), flatten: Some(flatten), resume,
+            ..ProviderConfig::new(ProviderLength::Epochs { epochs: 2 }, SourceDescription::Arrow(location.clone())) };
+
+        runs.push(test_util::run_provider(|tx| async move {loader.load_data(config, tx).await}).await.data);
+    }
+    for pair in runs.chunks(2) {
+        // The resume continues with the rows of the same shuffled order
+        assert_eq!(pair[0].len(), 40);
+        assert_eq!(pair[1], pair[0][27..].to_vec());
+    }
+}
+
+// Generator for the tests which rejects the odd values
+#[cfg(test)]
+struct EvenGenerator {}
+
+#[cfg(test)]
+impl ArrowGenerator for EvenGenerator {
+    type T = i64;
+    fn get_data(&self, batch:&RecordBatch) -> Option<i64> {
+        Some(batch.column(0).as_any().downcast_ref::<arrow::array::Int64Array>().unwrap().value(0)).filter(|e| e % 2 == 0)
+    }
+}
+
+#[tokio::test]
+async fn test_rejected_resume() {
+    use crate::provider::{test_util, provider_config::{ProviderLength, SourceDescription}};
+
+    // A file in the stream format with 4 batches of 5 rows where the odd rows are rejected
+    let dir = tempfile::tempdir().unwrap();
+    let batches:Vec<Vec<i64>> = (0..4).map(|batch| (0..5).map(|e| batch*5 + e).collect()).collect();
+    let location = test_util::write_arrow(&dir.path().join("data.arrow"), &batches, true);
+
+    // Resumed after 4 of the 10 accepted rows of the second epoch
+    let resume = DataCheckpoint { position: Some(DataPosition { epoch: 1, dataset: 0, offset: 4 }), documents: 14, ..Default::default() };
+    let mut runs = Vec::<Vec<i64>>::new();
+    for resume in [None, Some(resume)] {
+        let mut loader = ArrowTransfer::<i64>::new(location.clone(), 20).unwrap();
+        loader.generator = Some(Box::new(EvenGenerator{}));
+        let config = ProviderConfig { shuffle: Some(true), flatten: Some(false), seed: Some(5), resume,
+            ..ProviderConfig::new(ProviderLength::Epochs { epochs: 2 }, SourceDescription::Arrow(location.clone())) };
+
+        runs.push(test_util::run_provider(|tx| async move {loader.load_data(config, tx).await}).await.data);
+    }
+    // The resume continues after the accepted rows of the uninterrupted run
+    assert_eq!(runs[0].len(), 20);
+    assert_eq!(runs[1], runs[0][14..].to_vec());
+}
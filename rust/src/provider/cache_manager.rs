@@ -0,0 +1,244 @@
+use std::{path::{Path, PathBuf}, time::{Duration, SystemTime}, fs::File, io::Read};
+
+use sha2::{Digest, Sha256};
+
+use super::{ProviderChannel, general_file_provider::{self, DatasetOrder}, pile_datasets, cache_writer::{self, CacheManifest}, provider_util::{is_network, get_cache_name}, source_filter::SourceFilter, provider_config::{ProviderConfig, ProviderLength, SourceDescription, Dataset}};
+
+
+// Management of the cache directory used by the providers with --cache
+// Each cached source is a compressed data file with a manifest. Temporary files are left by downloads which didn't
+// complete and data files without a manifest by an interrupted finish
+
+// Temporary files newer than this can belong to a running download
+const TEMP_AGE:Duration = Duration::from_secs(3600);
+
+#[derive(Debug)]
+pub struct CacheEntry {
+    pub location:PathBuf, // Base path of the cache files
+    pub manifest:Option<CacheManifest>,
+    pub bytes:u64,
+    pub modified:SystemTime,
+    pub temporary:bool // Unfinished download
+}
+
+impl CacheEntry {
+    pub fn complete(&self) -> bool {
+        !self.temporary && self.manifest.is_some()
+    }
+
+    pub fn get_age(&self) -> Duration {
+        SystemTime::now().duration_since(self.modified).unwrap_or_default()
+    }
+
+    // Remove the files of the entry. The temporary file is kept if it's newer than TEMP_AGE as it can belong to a
+    // download of the source which is still running
+    pub fn remove(&self) {
+        for path in [cache_writer::get_cache_file(&self.location), cache_writer::get_manifest_file(&self.location)] {
+            let _ = std::fs::remove_file(path);
+        }
+        let temp = cache_writer::get_temp_file(&self.location);
+        if get_file_age(&temp).map(|e| e > TEMP_AGE).unwrap_or(false) {
+            let _ = std::fs::remove_file(temp);
+        }
+    }
+}
+
+fn get_file_age(path:&Path) -> Option<Duration> {
+    let modified = std::fs::metadata(path).and_then(|e| e.modified()).ok()?;
+    Some(SystemTime::now().duration_since(modified).unwrap_or_default())
+}
+
+fn create_entry(path:&Path) -> Option<CacheEntry> {
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let (base, temporary) = if let Some(x) = name.strip_suffix(".json.zst.tmp") {
+        (x.to_string(), true)
+    }
+    else {
+        (name.strip_suffix(".json.zst")?.to_string(), false)
+    };
+    let location = path.with_file_name(base);
+    let metadata = std::fs::metadata(path).ok()?;
+    Some(CacheEntry {
+        manifest: if temporary {None} else {cache_writer::load_manifest(&location)},
+        location,
+        bytes: metadata.len(),
+        modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        temporary
+    })
+}
+
+// Cache entries sorted from the oldest to the newest
+pub fn list_cache(cache:&str) -> Vec<CacheEntry> {
+    let mut entries:Vec<CacheEntry> = match std::fs::read_dir(cache) {
+        Ok(x) => x.filter_map(|e| e.ok()).filter_map(|e| create_entry(&e.path())).collect(),
+        Err(e) => {
+            log::error!("Can't Read Cache {} {:?}", cache, e);
+            vec![]
+        }
+    };
+    entries.sort_by_key(|e| e.modified);
+    entries
+}
+
+// Check the size and checksum of the data file against the manifest
+pub fn verify_entry(entry:&CacheEntry) -> Result<(), String> {
+    let manifest = entry.manifest.as_ref().ok_or("Missing Manifest".to_string())?;
+    if manifest.bytes != entry.bytes {
+        return Err(format!("Size {} doesn't match the Manifest {}", entry.bytes, manifest.bytes));
+    }
+    let mut file = File::open(cache_writer::get_cache_file(&entry.location)).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8;1 << 20];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => hasher.update(&buffer[..n]),
+            Err(e) => return Err(e.to_string())
+        }
+    }
+    let checksum = cache_writer::get_checksum(hasher);
+    if checksum != manifest.checksum {
+        return Err(format!("Checksum {} doesn't match the Manifest {}", checksum, manifest.checksum));
+    }
+    Ok(())
+}
+
+// Remove incomplete entries and the entries older than the maximum age. The oldest entries are then removed
+// until the total size is within the budget. Fresh incomplete entries can belong to a running download so they
+// are never removed and aren't part of the budget. Returns the removed entries
+pub fn prune_cache(cache:&str, max_age:Option<Duration>, max_size:Option<u64>) -> Vec<CacheEntry> {
+    let mut removed = Vec::<CacheEntry>::new();
+    let mut kept = Vec::<CacheEntry>::new();
+    for entry in list_cache(cache) {
+        if !entry.complete() && entry.get_age() <= TEMP_AGE {
+            continue;
+        }
+        let expired = max_age.map(|e| entry.get_age() > e).unwrap_or(false);
+        if !entry.complete() || expired {
+            removed.push(entry);
+        }
+        else if entry.complete() {
+            kept.push(entry);
+        }
+    }
+    if let Some(size) = max_size {
+        let mut total:u64 = kept.iter().map(|e| e.bytes).sum();
+        kept.reverse();
+        while total > size {
+            match kept.pop() {
+                Some(x) => {
+                    total -= x.bytes;
+                    removed.push(x);
+                },
+                None => break
+            }
+        }
+    }
+    removed.iter().for_each(|e| e.remove());
+    removed
+}
+
+// Datasets of a source including the components of a mixture
+pub fn get_sources(source:&SourceDescription) -> Vec<Dataset> {
+    match source {
+        SourceDescription::DataList(x) => x.clone(),
+        SourceDescription::Pile { typ } => pile_datasets::get_datasets(typ.clone()).unwrap_or_default(),
+        SourceDescription::Mixture(x) => x.components.iter().flat_map(|e| get_sources(&e.source)).collect(),
+        _ => vec![]
+    }
+}
+
+// Download the network sources of the configuration into the cache without training
+// Returns the number of sources which were cached
+pub async fn prewarm(config:&ProviderConfig, cache:&str) -> usize {
+    let filter = config.filter.clone().unwrap_or(SourceFilter::JsonText);
+    let mut count = 0;
+    for dataset in get_sources(&config.source).into_iter().filter(|e| is_network(&e.location)) {
+        let base = Path::new(cache).join(get_cache_name(&dataset.location, &filter));
+        if cache_writer::existing_cache_file(&base).is_some() {
+            log::info!("Already Cached {}", dataset.location);
+            count += 1;
+            continue;
+        }
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<ProviderChannel<String>>(256);
+        let location = dataset.location.clone();
+        let (filter, cache) = (filter.clone(), cache.to_string());
+        let handle = tokio::spawn(async move {
+            general_file_provider::load_data_sets(vec![dataset], ProviderLength::Epochs { epochs: 1 }, tx, Some(cache), &filter, None, DatasetOrder::default()).await;
+        });
+        while let Some(x) = rx.recv().await {
+            if let ProviderChannel::Complete = x {
+                break;
+            }
+        }
+        let _ = handle.await;
+        if cache_writer::existing_cache_file(&base).is_some() {
+            count += 1;
+        }
+        else {
+            log::error!("Failed to Cache {}", location);
+        }
+    }
+    count
+}
+
+
+#[test]
+pub fn test_cache_prune() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = dir.path().to_string_lossy().to_string();
+    let now = SystemTime::now();
+    for (index, name) in ["first", "second"].iter().enumerate() {
+        let mut writer = cache_writer::CacheWriter::new(dir.path().join(name), name).unwrap();
+        writer.write_line(name.repeat(100));
+        writer.finish();
+        let file = File::options().write(true).open(cache_writer::get_cache_file(&dir.path().join(name))).unwrap();
+        file.set_modified(now - Duration::from_secs(60 * (2 - index as u64))).unwrap();
+    }
+    let entries = list_cache(&cache);
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e.complete() && verify_entry(e).is_ok()));
+
+    // Modified data fails the checksum
+    let data = cache_writer::get_cache_file(&entries[1].location);
+    let mut bytes = std::fs::read(&data).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    std::fs::write(&data, bytes).unwrap();
+    assert!(verify_entry(&list_cache(&cache)[1]).is_err());
+
+    // A running download isn't part of the budget
+    std::fs::write(dir.path().join("third.json.zst.tmp"), vec![0u8;1000]).unwrap();
+
+    // The oldest entry is removed first
+    let removed = prune_cache(&cache, None, Some(entries[1].bytes));
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].location, entries[0].location);
+    let entries = list_cache(&cache);
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().any(|e| e.temporary));
+}
+
+#[test]
+pub fn test_prune_downloads() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = dir.path().to_string_lossy().to_string();
+    let old = SystemTime::now() - TEMP_AGE - Duration::from_secs(60);
+    let mut writer = cache_writer::CacheWriter::new(dir.path().join("first"), "first").unwrap();
+    writer.write_line("first".to_string());
+    writer.finish();
+    File::options().write(true).open(cache_writer::get_cache_file(&dir.path().join("first"))).unwrap().set_modified(old).unwrap();
+
+    // A download of the expired source and a new source which are still running along with an abandoned download
+    std::fs::write(dir.path().join("first.json.zst.tmp"), vec![0u8;100]).unwrap();
+    std::fs::write(dir.path().join("second.json.zst.tmp"), vec![0u8;100]).unwrap();
+    std::fs::write(dir.path().join("third.json.zst.tmp"), vec![0u8;100]).unwrap();
+    File::options().write(true).open(dir.path().join("third.json.zst.tmp")).unwrap().set_modified(old).unwrap();
+
+    let removed = prune_cache(&cache, Some(Duration::from_secs(0)), None);
+    assert_eq!(removed.len(), 2);
+    assert!(dir.path().join("first.json.zst.tmp").exists());
+    assert!(dir.path().join("second.json.zst.tmp").exists());
+    assert!(!dir.path().join("third.json.zst.tmp").exists());
+    assert!(!cache_writer::get_cache_file(&dir.path().join("first")).exists());
+}
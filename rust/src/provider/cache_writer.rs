@@ -1,61 +1,234 @@
-use std::{path::PathBuf, fs::File, io::BufWriter};
-use std::io::{Write};
+use std::{path::{Path, PathBuf}, fs::File, io::{BufWriter, Write}};
 
-use serde::Serialize;
-use std::process::{Command};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+
+// Local cache of a downloaded dataset stored as zstd compressed json lines
+// The lines are compressed while streaming into a temporary file which is renamed once the full source has been read
+// The manifest is written after the rename so a cache is only used when both files exist. An unfinished cache
+// (error or early stop of the provider) is removed when the writer is dropped
 
 #[derive(Serialize)]
 pub struct Data {
     pub text:String
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct CacheManifest {
+    pub source:String,
+    pub lines:u64,
+    pub bytes:u64, // Size of the compressed file
+    pub checksum:String // Sha256 of the compressed file
+}
+
+pub fn get_cache_file(path:&Path) -> PathBuf {
+    path.with_extension("json.zst")
+}
+
+pub fn get_manifest_file(path:&Path) -> PathBuf {
+    path.with_extension("manifest.json")
+}
+
+pub fn get_temp_file(path:&Path) -> PathBuf {
+    path.with_extension("json.zst.tmp")
+}
+
+pub fn get_checksum(hasher:Sha256) -> String {
+    hasher.finalize().iter().map(|e| format!("{:02x}", e)).collect()
+}
+
+// Writer which hashes and counts the compressed data
+pub struct HashWriter {
+    writer:BufWriter<File>,
+    hasher:Sha256,
+    bytes:u64
+}
+
+impl Write for HashWriter {
+    fn write(&mut self, buf:&[u8]) -> std::io::Result<usize> {
+        let n = self.writer.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.bytes += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
 pub struct CacheWriter {
-    pub location:PathBuf,
-    pub writer:BufWriter<File>
+    pub location:PathBuf, // Base path of the cache files
+    extension:String, // Extension of the data file
+    source:String,
+    encoder:Option<zstd::Encoder<'static, HashWriter>>,
+    lines:u64
 }
 
 impl CacheWriter {
 
-    pub fn new(path:PathBuf) -> Self {
-        let new_location = path.to_owned().with_extension("json");
-        log::info!("Creating File {:?}", &new_location);
+    pub fn new(path:PathBuf, source:&str) -> Option<Self> {
+        Self::with_extension(path, source, "json.zst")
+    }
+
+    pub fn with_extension(path:PathBuf, source:&str, extension:&str) -> Option<Self> {
+        let temp_location = path.with_extension(format!("{}.tmp", extension));
+        log::info!("Creating File {:?}", &temp_location);
 
-        let file = File::create(new_location).unwrap();
-        let writer = BufWriter::new(file);
-        Self {
+        let file = match File::create(&temp_location) {
+            Ok(x) => x,
+            Err(e) => {
+                log::error!("Can't Create Cache File {:?} {:?}", temp_location, e);
+                return None;
+            }
+        };
+        let writer = HashWriter { writer: BufWriter::new(file), hasher: Sha256::new(), bytes: 0 };
+        let encoder = match zstd::Encoder::new(writer, 0) {
+            Ok(x) => x,
+            Err(e) => {
+                log::error!("Can't Create Cache Encoder {:?} {:?}", temp_location, e);
+                let _ = std::fs::remove_file(&temp_location);
+                return None;
+            }
+        };
+        Some(Self {
             location:path,
-            writer:writer
-        }
+            extension:extension.to_string(),
+            source:source.to_string(),
+            encoder:Some(encoder),
+            lines:0
+        })
+    }
+
+    pub fn get_data_file(&self) -> PathBuf {
+        self.location.with_extension(&self.extension)
     }
 
-    pub fn write_line(&mut self, data:String) {
+    fn get_temp_file(&self) -> PathBuf {
+        self.location.with_extension(format!("{}.tmp", self.extension))
+    }
+
+    // Returns false if the line couldn't be written. The cache is removed after a failed write
+    pub fn write_line(&mut self, data:String) -> bool {
         let real_data = Data{text:data};
         let mut result = serde_json::to_vec(&real_data).unwrap();
         result.extend(b"\n");
-        let _ = self.writer.write_all(&result[0..result.len()]);
+        let encoder = match self.encoder.as_mut() {
+            Some(x) => x,
+            None => return false
+        };
+        if let Err(e) = encoder.write_all(&result[0..result.len()]) {
+            log::error!("Cache Write Failed {:?} {:?}", self.location, e);
+            self.remove();
+            return false;
+        }
+        self.lines += 1;
+        true
+    }
+
+    fn complete(&mut self, encoder:zstd::Encoder<'static, HashWriter>) -> std::io::Result<CacheManifest> {
+        let mut writer = encoder.finish()?;
+        writer.flush()?;
+        writer.writer.get_ref().sync_all()?;
+        std::fs::rename(self.get_temp_file(), self.get_data_file())?;
+
+        let manifest = CacheManifest {
+            source: self.source.clone(),
+            lines: self.lines,
+            bytes: writer.bytes,
+            checksum: get_checksum(writer.hasher)
+        };
+        let manifest_temp = get_manifest_file(&self.location).with_extension("tmp");
+        std::fs::write(&manifest_temp, serde_json::to_vec_pretty(&manifest)?)?;
+        std::fs::rename(manifest_temp, get_manifest_file(&self.location))?;
+        Ok(manifest)
     }
 
-    pub fn finish(&mut self) {
-        let _ = self.writer.flush();
-        let parent_folder = self.location.parent().unwrap().as_os_str();
-        let base_location = self.location.to_owned().with_extension("json").as_os_str().to_owned();
-        let args = vec![base_location];
-        let result = Command::new("zstd").current_dir(parent_folder).args(args).output();
-        let stderr = &result.unwrap().stdout[..]; 
-        let std_string = std::str::from_utf8(stderr).unwrap();
-        let _fail = std_string.find("FAILED");
-        
+    // Called once the full source has been read to make the cache available
+    pub fn finish(&mut self) -> bool {
+        let encoder = match self.encoder.take() {
+            Some(x) => x,
+            None => return false
+        };
+        match self.complete(encoder) {
+            Ok(x) => {
+                log::info!("Finished Cache {:?} with {} Lines", self.get_data_file(), x.lines);
+                true
+            },
+            Err(e) => {
+                log::error!("Cache Finish Failed {:?} {:?}", self.location, e);
+                let _ = std::fs::remove_file(self.get_temp_file());
+                false
+            }
+        }
+    }
+
+    fn remove(&mut self) {
+        if self.encoder.take().is_some() {
+            log::info!("Removing Incomplete Cache {:?}", self.get_temp_file());
+            let _ = std::fs::remove_file(self.get_temp_file());
+        }
     }
 
 }
 
+impl Drop for CacheWriter {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
+pub fn load_manifest(cached_path:&Path) -> Option<CacheManifest> {
+    let text = std::fs::read_to_string(get_manifest_file(cached_path)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+// Returns the cache file if it was completed
 pub fn existing_cache_file(cached_path:&PathBuf) -> Option<PathBuf> {
-    let zstd_file = cached_path.with_extension("json.zst");
-    
-    if zstd_file.exists() {
+    let zstd_file = get_cache_file(cached_path);
+    let manifest = load_manifest(cached_path)?;
+    let size = std::fs::metadata(&zstd_file).ok()?.len();
+
+    if size == manifest.bytes {
         Some(zstd_file)
     }
     else {
         None
     }
 }
+
+
+#[test]
+pub fn test_cache_finish() {
+    use std::io::Read;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache");
+
+    // Dropping an unfinished writer removes the temporary file
+    let mut writer = CacheWriter::new(path.clone(), "http://source").unwrap();
+    writer.write_line("first".to_string());
+    assert!(get_temp_file(&path).exists());
+    drop(writer);
+    assert!(!get_temp_file(&path).exists());
+    assert!(existing_cache_file(&path).is_none());
+
+    let mut writer = CacheWriter::new(path.clone(), "http://source").unwrap();
+    assert!(writer.write_line("first".to_string()));
+    assert!(writer.write_line("second".to_string()));
+    assert!(writer.finish());
+    // The finished writer doesn't take more lines
+    assert!(!writer.write_line("third".to_string()));
+    drop(writer);
+
+    let location = existing_cache_file(&path).unwrap();
+    let manifest = load_manifest(&path).unwrap();
+    assert_eq!(manifest.lines, 2);
+    assert_eq!(manifest.source, "http://source");
+    let data = std::fs::read(&location).unwrap();
+    assert_eq!(manifest.checksum, get_checksum(Sha256::new_with_prefix(&data)));
+    let mut text = String::new();
+    zstd::Decoder::new(&data[..]).unwrap().read_to_string(&mut text).unwrap();
+    assert_eq!(text, "{\"text\":\"first\"}\n{\"text\":\"second\"}\n");
+}
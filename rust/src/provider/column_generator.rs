@@ -0,0 +1,197 @@
+use std::sync::Arc;
+
+use arrow::{array::{Array, ArrayRef, StringArray, LargeStringArray, Int32Array, Int64Array, ListArray, LargeListArray}, datatypes::{Schema, DataType}, record_batch::RecordBatch};
+
+use crate::models::{simple_transport::SimpleTransport, simple_label::Label};
+
+use super::{arrow_transfer::ArrowGenerator, provider_config::{ColumnConfig, LabelType}};
+
+
+// Generic conversion of arrow columns based on the ColumnConfig
+// Supports Utf8/LargeUtf8 text, Int32/Int64 single labels and List/LargeList of Int32/Int64 multi labels
+
+pub fn is_text(typ:&DataType) -> bool {
+    matches!(typ, DataType::Utf8 | DataType::LargeUtf8)
+}
+
+fn is_int(typ:&DataType) -> bool {
+    matches!(typ, DataType::Int32 | DataType::Int64)
+}
+
+fn is_int_list(typ:&DataType) -> bool {
+    match typ {
+        DataType::List(x) | DataType::LargeList(x) => is_int(x.data_type()),
+        _ => false
+    }
+}
+
+// The column types are checked when the generator is created and files with different column types are skipped
+// by the ArrowTransfer so the values only return None for a type which isn't supported
+pub fn get_text(array:&ArrayRef, row:usize) -> Option<String> {
+    match array.data_type() {
+        DataType::Utf8 => Some(array.as_any().downcast_ref::<StringArray>().unwrap().value(row).to_string()),
+        DataType::LargeUtf8 => Some(array.as_any().downcast_ref::<LargeStringArray>().unwrap().value(row).to_string()),
+        _ => None
+    }
+}
+
+fn get_int(array:&ArrayRef, row:usize) -> Option<i64> {
+    match array.data_type() {
+        DataType::Int32 => Some(array.as_any().downcast_ref::<Int32Array>().unwrap().value(row) as i64),
+        DataType::Int64 => Some(array.as_any().downcast_ref::<Int64Array>().unwrap().value(row)),
+        _ => None
+    }
+}
+
+// Labels are class indices so negative values are rejected
+fn get_label(array:&ArrayRef, row:usize) -> Option<u32> {
+    let value = get_int(array, row)?;
+    match u32::try_from(value) {
+        Ok(x) => Some(x),
+        Err(_) => {
+            log::error!("Label {} Not Supported", value);
+            None
+        }
+    }
+}
+
+fn get_int_list(array:&ArrayRef, row:usize) -> Option<Vec<u32>> {
+    let values = match array.data_type() {
+        DataType::List(_) => array.as_any().downcast_ref::<ListArray>().unwrap().value(row),
+        DataType::LargeList(_) => array.as_any().downcast_ref::<LargeListArray>().unwrap().value(row),
+        _ => return None
+    };
+    (0..values.len()).map(|e| get_label(&values, e)).collect()
+}
+
+// Returns the column index checking that the type is supported
+pub fn get_column(schema:&Arc<Schema>, name:&str, check:fn(&DataType) -> bool) -> Result<usize, String> {
+    match schema.column_with_name(name) {
+        Some((index, field)) if check(field.data_type()) => Ok(index),
+        Some((_, field)) => Err(format!("Column {} Type {:?} Not Supported", name, field.data_type())),
+        None => Err(format!("Column {} Not Found in {:?}", name, schema))
+    }
+}
+
+
+// Generator which returns the text column for pretraining
+pub struct TextArrowGenerator {
+    pub t:usize
+}
+
+impl ArrowGenerator for TextArrowGenerator {
+    type T = String;
+    fn get_data(&self, data:&RecordBatch) -> Option<Self::T> {
+        get_text(data.column(self.t), 0)
+    }
+}
+
+impl TextArrowGenerator {
+    pub fn new(schema:&Arc<Schema>, column:&str) -> Result<Self, String> {
+        Ok(Self {
+            t: get_column(schema, column, is_text)?
+        })
+    }
+}
+
+
+// Generator for classification tasks with an optional text pair and label
+pub struct ColumnArrowGenerator {
+    pub t:usize, // Text Location
+    pub p:Option<usize>, // Pair Location
+    pub l:Option<(usize, LabelType)> // Label Location and Type
+}
+
+impl ArrowGenerator for ColumnArrowGenerator {
+    type T = SimpleTransport;
+    fn get_data(&self, data:&RecordBatch) -> Option<Self::T> {
+        let text = get_text(data.column(self.t), 0)?;
+        let pair = match self.p {
+            Some(p) => Some(get_text(data.column(p), 0)?),
+            None => None
+        };
+        let label:Option<Label> = match &self.l {
+            Some((l, LabelType::Single)) => Some(get_label(data.column(*l), 0)?.into()),
+            Some((l, LabelType::Multi)) => Some(get_int_list(data.column(*l), 0)?.into()),
+            None => None
+        };
+        Some(SimpleTransport{ data: (text, pair).into(), label })
+    }
+}
+
+impl ColumnArrowGenerator {
+
+    pub fn new(schema:&Arc<Schema>, config:&ColumnConfig) -> Result<Self, String> {
+        let label = match &config.label {
+            Some(name) => {
+                let label_type = match &config.label_type {
+                    Some(x) => x.clone(),
+                    None => {
+                        let list = schema.column_with_name(name).map(|e| is_int_list(e.1.data_type())).unwrap_or(false);
+                        if list {LabelType::Multi} else {LabelType::Single}
+                    }
+                };
+                let index = match label_type {
+                    LabelType::Single => get_column(schema, name, is_int)?,
+                    LabelType::Multi => get_column(schema, name, is_int_list)?,
+                };
+                Some((index, label_type))
+            },
+            None => None
+        };
+
+        let pair = match &config.pair {
+            Some(x) => Some(get_column(schema, x, is_text)?),
+            None => None
+        };
+        Ok(Self {
+            t: get_column(schema, &config.text, is_text)?,
+            p: pair,
+            l: label
+        })
+    }
+}
+
+
+#[test]
+pub fn test_column_generator() {
+    use arrow::{datatypes::Field, array::{ListBuilder, Int64Builder}};
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("sentence", DataType::LargeUtf8, false),
+        Field::new("context", DataType::Utf8, false),
+        Field::new("class", DataType::Int32, false),
+        Field::new("labels", DataType::List(Box::new(Field::new("item", DataType::Int64, true))), false),
+    ]));
+    let mut labels = ListBuilder::new(Int64Builder::new());
+    labels.values().append_slice(&[1, 3]);
+    labels.append(true);
+    let batch = RecordBatch::try_new(schema.clone(), vec![
+        Arc::new(LargeStringArray::from(vec!["first"])),
+        Arc::new(StringArray::from(vec!["second"])),
+        Arc::new(Int32Array::from(vec![2])),
+        Arc::new(labels.finish()),
+    ]).unwrap();
+
+    let single = ColumnArrowGenerator::new(&schema, &ColumnConfig::new("sentence", Some("context"), Some("class"), None)).unwrap();
+    let data = single.get_data(&batch).unwrap();
+    assert_eq!(data.data.text, "first");
+    assert_eq!(data.data.alt_text, Some("second".to_string()));
+    assert!(matches!(data.label, Some(Label::Single(2))));
+
+    let multi = ColumnArrowGenerator::new(&schema, &ColumnConfig::new("sentence", None, Some("labels"), None)).unwrap();
+    assert!(matches!(multi.get_data(&batch).unwrap().label, Some(Label::Multi(x)) if x == vec![1, 3]));
+
+    // Missing columns and columns with a different type are reported to the runner
+    assert!(ColumnArrowGenerator::new(&schema, &ColumnConfig::new("missing", None, None, None)).is_err());
+    assert!(TextArrowGenerator::new(&schema, "class").is_err());
+
+    // Rows with a negative label are skipped
+    let negative = RecordBatch::try_new(schema.clone(), vec![
+        Arc::new(LargeStringArray::from(vec!["first"])),
+        Arc::new(StringArray::from(vec!["second"])),
+        Arc::new(Int32Array::from(vec![-1])),
+        batch.column(3).clone(),
+    ]).unwrap();
+    assert!(single.get_data(&negative).is_none());
+}
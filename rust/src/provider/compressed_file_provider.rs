@@ -0,0 +1,221 @@
+use std::{path::PathBuf, pin::Pin, io::{Read, Cursor}};
+
+use super::{ProviderChannel, general_file_provider::Counter, url_reader::{self, DownloadConfig}, cache_writer::CacheWriter, source_filter::SourceFilter, provider_config::Dataset, provider_util::DownloadType};
+use async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder, XzDecoder, BzDecoder};
+use tokio::{io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader}, fs::File};
+
+use tokio::sync::mpsc::Sender;
+use tokio_util::io::{StreamReader, SyncIoBridge};
+
+
+// Line provider for compressed json lines files which is generic over the compression type
+// The codec is selected from the extension and falls back to the magic bytes at the start of the stream
+// Supports gzip, zstd, xz, bzip2 and lz4 (frame format) for both local files and urls
+
+pub type Decoder = Pin<Box<dyn AsyncRead + Send>>;
+
+// Compression type from the first bytes of the stream
+pub fn get_magic_type(header:&[u8]) -> DownloadType {
+    if header.starts_with(&[0x1f, 0x8b]) {
+        DownloadType::Gzip
+    }
+    else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        DownloadType::Zstd
+    }
+    else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        DownloadType::Xz
+    }
+    else if header.starts_with(b"BZh") {
+        DownloadType::Bzip2
+    }
+    else if header.starts_with(&[0x04, 0x22, 0x4d, 0x18]) {
+        DownloadType::Lz4
+    }
+    else {
+        DownloadType::Error
+    }
+}
+
+// async-compression doesn't support lz4 so the frame decoder runs on a blocking thread
+fn create_lz4_decoder<R:AsyncRead + Send + Unpin + 'static>(reader:R) -> impl AsyncRead + Send {
+    let (tx, rx) = tokio::sync::mpsc::channel::<std::io::Result<Cursor<Vec<u8>>>>(4);
+    let bridge = SyncIoBridge::new(reader);
+    tokio::task::spawn_blocking(move || {
+        let mut decoder = match lz4::Decoder::new(bridge) {
+            Ok(x) => x,
+            Err(e) => {
+                let _ = tx.blocking_send(Err(e));
+                return;
+            }
+        };
+        loop {
+            let mut buffer = vec![0u8;65536];
+            match decoder.read(&mut buffer) {
+                Ok(0) => return,
+                Ok(n) => {
+                    buffer.truncate(n);
+                    if tx.blocking_send(Ok(Cursor::new(buffer))).is_err() {
+                        return;
+                    }
+                },
+                Err(e) => {
+                    let _ = tx.blocking_send(Err(e));
+                    return;
+                }
+            }
+        }
+    });
+    StreamReader::new(futures::stream::unfold(rx, |mut rx| async move {rx.recv().await.map(|e| (e, rx))}))
+}
+
+// Wraps the reader with the decoder for the compression type (checking the magic bytes if the type is unknown)
+pub async fn create_decoder<R:AsyncBufRead + Send + Unpin + 'static>(mut reader:R, typ:DownloadType) -> Option<Decoder> {
+    let typ = match typ {
+        DownloadType::Error => get_magic_type(reader.fill_buf().await.ok()?),
+        x => x
+    };
+    // Multiple members allows concatenated streams such as multistream bz2 wikipedia dumps
+    match typ {
+        DownloadType::Gzip => {
+            let mut decoder = GzipDecoder::new(reader);
+            decoder.multiple_members(true);
+            Some(Box::pin(decoder))
+        },
+        DownloadType::Zstd => {
+            let mut decoder = ZstdDecoder::new(reader);
+            decoder.multiple_members(true);
+            Some(Box::pin(decoder))
+        },
+        DownloadType::Xz => {
+            let mut decoder = XzDecoder::new(reader);
+            decoder.multiple_members(true);
+            Some(Box::pin(decoder))
+        },
+        DownloadType::Bzip2 => {
+            let mut decoder = BzDecoder::new(reader);
+            decoder.multiple_members(true);
+            Some(Box::pin(decoder))
+        },
+        DownloadType::Lz4 => Some(Box::pin(create_lz4_decoder(reader))),
+        x => {
+            log::error!("Compression Type Not Supported {:?}", x);
+            None
+        }
+    }
+}
+
+async fn send_lines(decoder:Decoder,
+    counter:&mut Counter,
+    tx:&Sender<ProviderChannel<String>>,
+    mut cache_writer:Option<CacheWriter>,
+    filter:&SourceFilter) {
+
+    let buf_reader = BufReader::with_capacity(100000, decoder);
+    let mut lines = buf_reader.lines();
+
+    let mut total_count:u32 = 0;
+    let mut count:u32 = 0;
+    loop {
+        let data = lines.next_line().await;
+        match data {
+            Ok(Some(line)) => {
+                total_count += 1;
+                let text = filter.parse_text(line);
+                match text {
+                    Ok(Some(x)) => {
+                        if count % 65536 == 1 {
+                            log::info!("Processed {:?} Lines out of {:?}", count, total_count);
+                        }
+                        if let Some(writer) = cache_writer.as_mut() {
+                            writer.write_line(x.to_owned());
+                        }
+                        if counter.skip_data() {
+                            continue;
+                        }
+                        let _res = tx.send(ProviderChannel::Data(x)).await;
+                        if counter.inc_data() {
+                            return;
+                        }
+                        count += 1;
+                    },
+                    Ok(None) => {
+                        continue
+                    },
+                    Err(e) => counter.inc_invalid(&e)
+                }
+            },
+            Ok(None) => {
+                log::info!("Line Not Available");
+                // The cache is only kept if the full source was read
+                if let Some(writer) = cache_writer.as_mut() {
+                    writer.finish();
+                }
+                return;
+            },
+            Err(e) => {
+                log::error!("Error in File Read {:?}", e);
+                counter.errors += 1;
+                return;
+            },
+        }
+    }
+}
+
+pub async fn load_dataset(path:&PathBuf, typ:DownloadType, counter:&mut Counter, tx:&Sender<ProviderChannel<String>>, filter:&SourceFilter) {
+    let file = match File::open(path).await {
+        Ok(x) => x,
+        Err(_) => {
+            log::error!("File Not Found {:?}", path);
+            counter.errors += 1;
+            return;
+        }
+    };
+    match create_decoder(BufReader::new(file), typ).await {
+        Some(decoder) => send_lines(decoder, counter, tx, None, filter).await,
+        None => counter.errors += 1
+    }
+}
+
+pub async fn load_url(dataset:&Dataset,
+    typ:DownloadType,
+    counter:&mut Counter,
+    tx:&Sender<ProviderChannel<String>>,
+    cache_writer:Option<CacheWriter>,
+    filter:&SourceFilter) {
+
+    let stream = url_reader::create_url_reader(&dataset.location, DownloadConfig::default());
+    match create_decoder(BufReader::new(stream), typ).await {
+        Some(decoder) => send_lines(decoder, counter, tx, cache_writer, filter).await,
+        None => counter.errors += 1
+    }
+}
+
+
+#[tokio::test]
+async fn test_codecs() {
+    use std::io::Write;
+    use super::provider_config::ProviderLength;
+
+    let dir = tempfile::tempdir().unwrap();
+    let data = b"{\"text\":\"first\"}\n{\"text\":\"second\"}\n";
+
+    let xz_path = dir.path().join("data.xz");
+    let mut compressed = Vec::<u8>::new();
+    tokio::io::AsyncReadExt::read_to_end(&mut async_compression::tokio::bufread::XzEncoder::new(&data[..]), &mut compressed).await.unwrap();
+    std::fs::write(&xz_path, compressed).unwrap();
+
+    let lz4_path = dir.path().join("data.bin");
+    let mut encoder = lz4::EncoderBuilder::new().build(std::fs::File::create(&lz4_path).unwrap()).unwrap();
+    encoder.write_all(data).unwrap();
+    encoder.finish().1.unwrap();
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<String>>(16);
+    let mut counter = Counter::new(ProviderLength::Epochs { epochs: 1 });
+    load_dataset(&xz_path, DownloadType::Xz, &mut counter, &tx, &SourceFilter::JsonText).await;
+    // Unknown extension which uses the magic bytes
+    load_dataset(&lz4_path, DownloadType::Error, &mut counter, &tx, &SourceFilter::JsonText).await;
+    drop(tx);
+
+    let values = super::test_util::collect_channel(rx).await.data;
+    assert_eq!(values, vec!["first", "second", "first", "second"]);
+}
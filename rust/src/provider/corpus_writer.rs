@@ -0,0 +1,220 @@
+use std::path::PathBuf;
+
+use super::{ProviderChannel, dedup, quality_filter, cache_writer::CacheWriter, mixture_provider::ComponentProvider, provider_config::{ProviderConfig, ProviderLength}};
+
+
+// Writes the documents of a provider after the filtering stages to a directory of shards which can be used as a
+// DataList source. Each shard is a zstd compressed json lines file with a manifest, written through the cache
+// writer so an interrupted run leaves no partial shard behind. The shards are written to a staging directory which
+// is renamed to the output once every shard is finished so a failed run leaves no output
+
+pub const DEFAULT_SHARD_SIZE:u64 = 1 << 30;
+
+pub struct ShardWriter {
+    directory:PathBuf,
+    staging:PathBuf, // Directory of the shards until the output is complete
+    source:String,
+    max_bytes:u64, // Size of the text after which a new shard is started
+    writer:Option<CacheWriter>,
+    bytes:u64, // Size of the text in the current shard
+    pub shards:Vec<PathBuf>,
+    pub documents:u64
+}
+
+impl ShardWriter {
+    // Returns None if the output directory isn't empty so old shards aren't mixed with the new ones
+    pub fn new(directory:&str, source:&str, max_bytes:u64) -> Option<Self> {
+        if std::fs::read_dir(directory).map(|mut e| e.next().is_some()).unwrap_or(false) {
+            log::error!("Output {} isn't Empty", directory);
+            return None;
+        }
+        let staging = PathBuf::from(format!("{}.partial", directory.trim_end_matches('/')));
+        // Shards of an earlier failed run
+        let _ = std::fs::remove_dir_all(&staging);
+        if let Err(e) = std::fs::create_dir_all(&staging) {
+            log::error!("Can't Create Output {:?} {:?}", staging, e);
+            return None;
+        }
+        Some(Self { directory: PathBuf::from(directory), staging, source: source.to_string(), max_bytes, writer: None, bytes: 0, shards: vec![], documents: 0 })
+    }
+
+    fn finish_shard(&mut self) -> bool {
+        match self.writer.take() {
+            Some(mut x) => {
+                self.bytes = 0;
+                let result = x.finish();
+                if result {
+                    self.shards.push(x.get_data_file());
+                }
+                result
+            },
+            None => true
+        }
+    }
+
+    pub fn write(&mut self, text:String) -> bool {
+        if self.writer.is_none() {
+            let location = self.staging.join(format!("part-{:05}", self.shards.len()));
+            self.writer = CacheWriter::with_extension(location, &self.source, "jsonl.zst");
+        }
+        let writer = match self.writer.as_mut() {
+            Some(x) => x,
+            None => return false
+        };
+        self.bytes += text.len() as u64;
+        self.documents += 1;
+        if !writer.write_line(text) {
+            return false;
+        }
+        if self.bytes >= self.max_bytes {
+            return self.finish_shard();
+        }
+        true
+    }
+
+    // Completes the last shard and moves the shards to the output. Returns the shards or None if the output
+    // couldn't be written
+    pub fn finish(mut self) -> Option<Vec<PathBuf>> {
+        if !self.finish_shard() {
+            self.abort();
+            return None;
+        }
+        // An empty output directory is replaced
+        let _ = std::fs::remove_dir(&self.directory);
+        if let Err(e) = std::fs::rename(&self.staging, &self.directory) {
+            log::error!("Can't Move {:?} to {:?} {:?}", self.staging, self.directory, e);
+            self.abort();
+            return None;
+        }
+        Some(self.shards.iter().filter_map(|e| e.file_name()).map(|e| self.directory.join(e)).collect())
+    }
+
+    // Removes the shards of a failed run
+    pub fn abort(self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.staging) {
+            log::error!("Can't Remove {:?} {:?}", self.staging, e);
+        }
+    }
+}
+
+// Runs the provider for a single epoch with the quality and dedup stages and writes the remaining documents
+// Returns the written shards or None if the output is incomplete
+pub async fn write_corpus(mut config:ProviderConfig, create:ComponentProvider<String>, cache:Option<String>, mut writer:ShardWriter) -> Option<Vec<PathBuf>> {
+    config.length = ProviderLength::Epochs { epochs: 1 };
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<String>>(256);
+    let (quality, dedup) = (config.quality.clone(), config.dedup.clone());
+    let quality = match quality.map(quality_filter::QualityFilter::new).transpose() {
+        Ok(x) => x,
+        Err(e) => {
+            log::error!("{}", e);
+            writer.abort();
+            return None;
+        }
+    };
+    let provider = create(config, tx, cache);
+
+    let (rx, join_quality) = match quality {
+        Some(x) => {
+            let (rx_quality, handle) = quality_filter::create_quality_filter(x, rx);
+            (rx_quality, Some(handle))
+        },
+        None => (rx, None)
+    };
+    let (mut rx, join_dedup) = match dedup {
+        Some(x) => {
+            let (rx_dedup, handle) = dedup::create_dedup(x, rx);
+            (rx_dedup, Some(handle))
+        },
+        None => (rx, None)
+    };
+
+    let mut success = true;
+    while let Some(data) = rx.recv().await {
+        match data {
+            ProviderChannel::Data(x) => success = writer.write(x),
+            // A source which wasn't fully read would leave the corpus incomplete
+            ProviderChannel::Error(x) => {
+                log::error!("Source {} Failed", x);
+                success = false;
+            },
+            ProviderChannel::Complete => break,
+            _ => {}
+        }
+        if !success {
+            break;
+        }
+    }
+    drop(rx);
+    if !success {
+        // The providers don't stop when the receiver is closed so they are aborted instead of reading the full source
+        provider.abort();
+        join_quality.iter().for_each(|e| e.abort());
+        join_dedup.iter().for_each(|e| e.abort());
+        writer.abort();
+        return None;
+    }
+    // A stage which panicked may have stopped before the end of the data
+    let mut joined = provider.await.map_err(|e| e.to_string());
+    if let Some(x) = join_quality {
+        joined = joined.and(x.await.map(|_| ()).map_err(|e| e.to_string()));
+    }
+    if let Some(x) = join_dedup {
+        joined = joined.and(x.await.map(|_| ()).map_err(|e| e.to_string()));
+    }
+    if let Err(e) = joined {
+        log::error!("Corpus Stage Failed {}", e);
+        writer.abort();
+        return None;
+    }
+    let documents = writer.documents;
+    let shards = writer.finish()?;
+    log::info!("Wrote {} Documents to {} Shards", documents, shards.len());
+    Some(shards)
+}
+
+
+#[tokio::test]
+async fn test_write_corpus() {
+    use std::io::Read;
+    use crate::tasks::masking::masking_runner::create_provider;
+    use super::{test_util, provider_config::{SourceDescription, Dataset}, quality_filter::QualityConfig};
+
+    let dir = tempfile::tempdir().unwrap();
+    let documents:Vec<String> = (0..200).map(|i| if i % 4 == 0 {"short".to_string()} else {format!("document {} with enough words to pass", i)}).collect();
+    let input = test_util::write_json_lines(&dir.path().join("input.jsonl"), &documents);
+
+    let dataset = Dataset::from(input.as_str());
+    let config = ProviderConfig { quality: Some(QualityConfig { min_words: Some(3), ..Default::default() }),
+        ..ProviderConfig::new(ProviderLength::Epochs { epochs: 3 }, SourceDescription::DataList(vec![dataset])) };
+    let output = dir.path().join("output");
+    let writer = ShardWriter::new(&output.to_string_lossy(), "test", 200).unwrap();
+    let shards = write_corpus(config, create_provider, None, writer).await.unwrap();
+    assert!(shards.len() > 1);
+    assert!(shards.iter().all(|e| e.starts_with(&output) && e.exists()));
+    assert!(!dir.path().join("output.partial").exists());
+    // The output isn't reused
+    assert!(ShardWriter::new(&output.to_string_lossy(), "test", 200).is_none());
+
+    let mut texts = Vec::<String>::new();
+    for shard in &shards {
+        let mut text = String::new();
+        zstd::Decoder::new(std::fs::File::open(shard).unwrap()).unwrap().read_to_string(&mut text).unwrap();
+        texts.extend(text.lines().map(|e| serde_json::from_str::<serde_json::Value>(e).unwrap()["text"].as_str().unwrap().to_string()));
+        assert!(super::cache_writer::load_manifest(&shard.with_extension("").with_extension("")).is_some());
+    }
+    // A single epoch without the short documents
+    assert_eq!(texts.len(), 150);
+    assert!(texts.iter().all(|e| e != "short"));
+
+    // A source which ends early fails the run without an output
+    let truncated = dir.path().join("truncated.jsonl.gz");
+    let mut compressed = Vec::<u8>::new();
+    tokio::io::AsyncReadExt::read_to_end(&mut async_compression::tokio::bufread::GzipEncoder::new(&std::fs::read(&input).unwrap()[..]), &mut compressed).await.unwrap();
+    std::fs::write(&truncated, &compressed[..compressed.len() / 2]).unwrap();
+    let config = ProviderConfig::new(ProviderLength::Epochs { epochs: 1 }, SourceDescription::DataList(vec![Dataset::from(truncated.to_string_lossy().as_ref())]));
+    let failed = dir.path().join("failed");
+    let writer = ShardWriter::new(&failed.to_string_lossy(), "test", 200).unwrap();
+    assert!(write_corpus(config, create_provider, None, writer).await.is_none());
+    assert!(!failed.exists());
+    assert!(!dir.path().join("failed.partial").exists());
+}
@@ -0,0 +1,255 @@
+use std::{collections::{HashMap, VecDeque, hash_map::DefaultHasher}, hash::{Hash, Hasher}};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc::{Sender, Receiver}, task::{self, JoinHandle}};
+
+use crate::random::{self, RandomStream};
+
+use super::{ProviderChannel, DocumentText};
+
+
+// Near duplicate filtering of streamed documents with MinHash and locality sensitive hashing
+// Each document is reduced to a signature of the minimum hashes of its word shingles under a set of permutations
+// The signature is split into bands and documents which share a band are compared. A document is dropped when the
+// estimated Jaccard similarity to a document in the window of recently kept documents is over the threshold
+
+const PRIME:u64 = (1 << 61) - 1;
+
+fn default_threshold() -> f64 {0.8}
+fn default_shingle() -> usize {5}
+fn default_permutations() -> usize {128}
+fn default_bands() -> usize {16}
+fn default_window() -> usize {100000}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DedupConfig {
+    #[serde(default = "default_threshold")]
+    pub threshold:f64, // Minimum Jaccard similarity of a duplicate
+    #[serde(default = "default_shingle")]
+    pub shingle:usize, // Words in a shingle
+    #[serde(default = "default_permutations")]
+    pub permutations:usize, // Size of the signature which should be a multiple of the bands
+    #[serde(default = "default_bands")]
+    pub bands:usize,
+    #[serde(default = "default_window")]
+    pub window:usize // Number of kept documents which are compared against
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            threshold: default_threshold(),
+            shingle: default_shingle(),
+            permutations: default_permutations(),
+            bands: default_bands(),
+            window: default_window()
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DedupStats {
+    pub documents:u64,
+    pub dropped:u64
+}
+
+struct Entry {
+    signature:Vec<u64>,
+    keys:Vec<u64> // Hash of each band
+}
+
+pub struct Deduplicator {
+    config:DedupConfig,
+    rows:usize, // Signature values in a band
+    permutations:Vec<(u64, u64)>,
+    buckets:Vec<HashMap<u64, Vec<u64>>>, // Documents for the key of each band
+    entries:HashMap<u64, Entry>,
+    order:VecDeque<u64>,
+    index:u64,
+    pub stats:DedupStats
+}
+
+impl Deduplicator {
+    pub fn new(config:DedupConfig) -> Self {
+        let bands = config.bands.clamp(1, std::cmp::max(config.permutations, 1));
+        let rows = std::cmp::max(config.permutations / bands, 1);
+        // Fixed seed so the dropped documents don't depend on the training seed
+        let mut rng = random::create_rng(Some(0), RandomStream::Dedup, 0);
+        let permutations = (0..bands * rows).map(|_| (rng.gen_range(1..PRIME), rng.gen_range(0..PRIME))).collect();
+        Self {
+            config,
+            rows,
+            permutations,
+            buckets: (0..bands).map(|_| HashMap::new()).collect(),
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            index: 0,
+            stats: DedupStats::default()
+        }
+    }
+
+    fn get_shingles(&self, text:&str) -> Vec<u64> {
+        let words:Vec<u64> = text.split_whitespace().map(|e| {
+            let mut hasher = DefaultHasher::new();
+            e.chars().flat_map(|c| c.to_lowercase()).for_each(|c| c.hash(&mut hasher));
+            hasher.finish()
+        }).collect();
+        // Documents shorter than a shingle are a single shingle
+        let size = std::cmp::min(std::cmp::max(self.config.shingle, 1), words.len());
+        if size == 0 {
+            return vec![];
+        }
+        words.windows(size).map(|e| {
+            let mut hasher = DefaultHasher::new();
+            e.hash(&mut hasher);
+            hasher.finish()
+        }).collect()
+    }
+
+    pub fn get_signature(&self, text:&str) -> Option<Vec<u64>> {
+        let shingles = self.get_shingles(text);
+        if shingles.is_empty() {
+            return None;
+        }
+        Some(self.permutations.iter().map(|(a, b)| {
+            shingles.iter().map(|x| ((*a as u128 * (x % PRIME) as u128 + *b as u128) % PRIME as u128) as u64).min().unwrap_or(u64::MAX)
+        }).collect())
+    }
+
+    fn get_keys(&self, signature:&[u64]) -> Vec<u64> {
+        signature.chunks(self.rows).map(|e| {
+            let mut hasher = DefaultHasher::new();
+            e.hash(&mut hasher);
+            hasher.finish()
+        }).collect()
+    }
+
+    fn similarity(first:&[u64], second:&[u64]) -> f64 {
+        let equal = first.iter().zip(second).filter(|(a, b)| a == b).count();
+        equal as f64 / first.len() as f64
+    }
+
+    fn is_duplicate(&self, signature:&[u64], keys:&[u64]) -> bool {
+        keys.iter().enumerate()
+            .filter_map(|(band, key)| self.buckets[band].get(key))
+            .flatten()
+            .filter_map(|e| self.entries.get(e))
+            .any(|e| Self::similarity(signature, &e.signature) >= self.config.threshold)
+    }
+
+    fn remove_oldest(&mut self) {
+        let entry = match self.order.pop_front().and_then(|e| self.entries.remove(&e).map(|x| (e, x))) {
+            Some(x) => x,
+            None => return
+        };
+        for (band, key) in entry.1.keys.iter().enumerate() {
+            if let Some(ids) = self.buckets[band].get_mut(key) {
+                ids.retain(|e| *e != entry.0);
+                if ids.is_empty() {
+                    self.buckets[band].remove(key);
+                }
+            }
+        }
+    }
+
+    // Returns false if the text is a near duplicate of a document in the window
+    pub fn keep(&mut self, text:&str) -> bool {
+        self.stats.documents += 1;
+        let signature = match self.get_signature(text) {
+            Some(x) => x,
+            None => return true
+        };
+        let keys = self.get_keys(&signature);
+        if self.is_duplicate(&signature, &keys) {
+            self.stats.dropped += 1;
+            return false;
+        }
+        let index = self.index;
+        self.index += 1;
+        for (band, key) in keys.iter().enumerate() {
+            self.buckets[band].entry(*key).or_default().push(index);
+        }
+        self.entries.insert(index, Entry { signature, keys });
+        self.order.push_back(index);
+        while self.order.len() > std::cmp::max(self.config.window, 1) {
+            self.remove_oldest();
+        }
+        true
+    }
+}
+
+pub async fn dedup_data<T:DocumentText>(config:DedupConfig, mut rx:Receiver<ProviderChannel<T>>, tx:Sender<ProviderChannel<T>>) -> DedupStats {
+    let mut dedup = Deduplicator::new(config);
+
+    while let Some(data) = rx.recv().await {
+        match data {
+            ProviderChannel::Data(x) => {
+                if let Some(text) = x.document_text() {
+                    if !dedup.keep(text) {
+                        continue;
+                    }
+                }
+                if tx.send(ProviderChannel::Data(x)).await.is_err() {
+                    break;
+                }
+            },
+            ProviderChannel::Info(x) => {
+                let _ = tx.send(ProviderChannel::Info(x)).await;
+            },
+            ProviderChannel::Error(x) => {
+                let _ = tx.send(ProviderChannel::Error(x)).await;
+            },
+            ProviderChannel::Complete => {
+                let _ = tx.send(ProviderChannel::Complete).await;
+                break;
+            },
+            // Positions count the documents before filtering
+            ProviderChannel::Position(_) | ProviderChannel::Checkpoint(_) => {}
+        }
+    }
+    let stats = dedup.stats;
+    let percent = if stats.documents > 0 {100.0 * stats.dropped as f64 / stats.documents as f64} else {0.0};
+    log::info!("Dedup : {} Documents with {} Near Duplicates Dropped ({:.2}%)", stats.documents, stats.dropped, percent);
+    stats
+}
+
+// Start the dedup stage returning the receiver for the filtered data
+pub fn create_dedup<T:DocumentText + Send + 'static>(config:DedupConfig, rx:Receiver<ProviderChannel<T>>) -> (Receiver<ProviderChannel<T>>, JoinHandle<DedupStats>) {
+    let (tx_dedup, rx_dedup) = tokio::sync::mpsc::channel::<ProviderChannel<T>>(2);
+    let handle = task::spawn(async move {
+        dedup_data(config, rx, tx_dedup).await
+    });
+    (rx_dedup, handle)
+}
+
+
+#[tokio::test]
+async fn test_dedup() {
+    let base = "the quick brown fox jumps over the lazy dog while the cat sleeps on the warm mat near the door of the old house";
+    let documents = vec![
+        base.to_string(),
+        base.replace("old house", "old barn"), // Near duplicate
+        base.to_uppercase(), // Shingles ignore case
+        "a completely different document about the weather and the rain which falls on the hills every spring".to_string(),
+        base.replace("quick brown fox", "slow green turtle").replace("warm mat", "cold floor"),
+        String::new()
+    ];
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<String>>(2);
+    let sent = documents.clone();
+    tokio::spawn(async move {
+        for x in sent {
+            let _ = tx.send(ProviderChannel::Data(x)).await;
+        }
+        let _ = tx.send(ProviderChannel::Complete).await;
+    });
+    let (rx_dedup, handle) = create_dedup(DedupConfig::default(), rx);
+    let values = super::test_util::collect_channel(rx_dedup).await.data;
+    assert_eq!(values, vec![documents[0].clone(), documents[3].clone(), documents[4].clone(), String::new()]);
+    assert_eq!(handle.await.unwrap(), DedupStats { documents: 6, dropped: 2 });
+
+    // Documents outside of the window are kept
+    let mut dedup = Deduplicator::new(DedupConfig { window: 1, ..Default::default() });
+    assert!(dedup.keep(base) && dedup.keep(&documents[3]) && dedup.keep(base));
+    assert!(!dedup.keep(base));
+}
@@ -0,0 +1,197 @@
+use std::borrow::Cow;
+
+use memchr::{memchr, memchr2};
+
+
+// Extracts a single string field from a json object without building a serde_json::Value
+// The object is scanned at the top level skipping nested values. The text is borrowed from the line unless
+// it contains escapes which are decoded (including \u escapes with surrogate pairs). Lines which aren't a json
+// object are an error like the lines which serde_json can't parse
+
+fn skip_whitespace(bytes:&[u8], mut pos:usize) -> usize {
+    while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+        pos += 1;
+    }
+    pos
+}
+
+// Scans the string starting at the opening quote. Returns the position after the closing quote and if there are escapes
+fn scan_string(bytes:&[u8], start:usize) -> Option<(usize, bool)> {
+    let mut pos = start + 1;
+    let mut escaped = false;
+    loop {
+        pos += memchr2(b'"', b'\\', bytes.get(pos..)?)?;
+        if bytes[pos] == b'"' {
+            return Some((pos + 1, escaped));
+        }
+        escaped = true;
+        pos += 2;
+    }
+}
+
+// Position after the value at pos
+fn skip_value(bytes:&[u8], pos:usize) -> Option<usize> {
+    match bytes.get(pos)? {
+        b'"' => scan_string(bytes, pos).map(|e| e.0),
+        b'{' | b'[' => {
+            let mut depth = 0;
+            let mut pos = pos;
+            while pos < bytes.len() {
+                match bytes[pos] {
+                    b'"' => {
+                        pos = scan_string(bytes, pos)?.0;
+                        continue;
+                    },
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(pos + 1);
+                        }
+                    },
+                    _ => {}
+                }
+                pos += 1;
+            }
+            None
+        },
+        _ => {
+            let mut pos = pos;
+            while pos < bytes.len() && !matches!(bytes[pos], b',' | b'}' | b']') && !bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            Some(pos)
+        }
+    }
+}
+
+fn read_hex(text:&str, pos:usize) -> Option<u32> {
+    u32::from_str_radix(text.get(pos..pos + 4)?, 16).ok()
+}
+
+// Decodes the escapes of the contents of a json string
+pub fn unescape(text:&str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    let mut pos = 0;
+    while let Some(next) = memchr(b'\\', &bytes[pos..]) {
+        pos += next;
+        result.push_str(&text[last..pos]);
+        let escape = *bytes.get(pos + 1)?;
+        pos += 2;
+        match escape {
+            b'"' => result.push('"'),
+            b'\\' => result.push('\\'),
+            b'/' => result.push('/'),
+            b'b' => result.push('\u{8}'),
+            b'f' => result.push('\u{c}'),
+            b'n' => result.push('\n'),
+            b'r' => result.push('\r'),
+            b't' => result.push('\t'),
+            b'u' => {
+                let mut code = read_hex(text, pos)?;
+                pos += 4;
+                // Characters outside of the basic plane are a pair of surrogates
+                if (0xd800..0xdc00).contains(&code) && text.get(pos..pos + 2) == Some("\\u") {
+                    let low = read_hex(text, pos + 2)?;
+                    if (0xdc00..0xe000).contains(&low) {
+                        code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                        pos += 6;
+                    }
+                }
+                result.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+            },
+            _ => return None
+        }
+        last = pos;
+    }
+    result.push_str(&text[last..]);
+    Some(result)
+}
+
+fn invalid(message:&str) -> serde_json::Error {
+    serde::de::Error::custom(message)
+}
+
+// Range of the string value of the key in the top level object and if it has escapes. None if the key doesn't exist
+// or isn't a string. Lines which aren't a json object are an error
+fn find_string(line:&str, key:&str) -> Result<Option<(usize, usize, bool)>, serde_json::Error> {
+    let bytes = line.as_bytes();
+    let mut pos = skip_whitespace(bytes, 0);
+    if bytes.get(pos) != Some(&b'{') {
+        return Err(invalid("Expected an Object"));
+    }
+    pos = skip_whitespace(bytes, pos + 1);
+    if bytes.get(pos) == Some(&b'}') {
+        return Ok(None);
+    }
+    loop {
+        pos = skip_whitespace(bytes, pos);
+        if bytes.get(pos) != Some(&b'"') {
+            return Err(invalid("Expected a Key"));
+        }
+        let (end, escaped) = scan_string(bytes, pos).ok_or_else(|| invalid("Unterminated String"))?;
+        let name = &line[pos + 1..end - 1];
+        let matched = if escaped {unescape(name).ok_or_else(|| invalid("Invalid Escape"))? == key} else {name == key};
+        pos = skip_whitespace(bytes, end);
+        if bytes.get(pos) != Some(&b':') {
+            return Err(invalid("Expected a Colon"));
+        }
+        pos = skip_whitespace(bytes, pos + 1);
+        if matched {
+            if bytes.get(pos) != Some(&b'"') {
+                return Ok(None);
+            }
+            let (end, escaped) = scan_string(bytes, pos).ok_or_else(|| invalid("Unterminated String"))?;
+            return Ok(Some((pos + 1, end - 1, escaped)));
+        }
+        pos = skip_whitespace(bytes, skip_value(bytes, pos).ok_or_else(|| invalid("Invalid Value"))?);
+        match bytes.get(pos) {
+            Some(b',') => pos += 1,
+            Some(b'}') => return Ok(None),
+            _ => return Err(invalid("Expected a Comma"))
+        }
+    }
+}
+
+// String value of the key in the top level object which is borrowed from the line unless it has escapes
+pub fn extract_string<'a>(line:&'a str, key:&str) -> Result<Option<Cow<'a, str>>, serde_json::Error> {
+    match find_string(line, key)? {
+        Some((start, end, false)) => Ok(Some(Cow::Borrowed(&line[start..end]))),
+        Some((start, end, true)) => unescape(&line[start..end]).map(|e| Some(Cow::Owned(e))).ok_or_else(|| invalid("Invalid Escape")),
+        None => Ok(None)
+    }
+}
+
+// Text of the key where the buffer of the line is reused for text without escapes
+pub fn get_text(mut line:String, key:&str) -> Result<Option<String>, serde_json::Error> {
+    match find_string(&line, key)? {
+        Some((start, end, false)) => {
+            line.truncate(end);
+            line.replace_range(..start, "");
+            Ok(Some(line))
+        },
+        Some((start, end, true)) => unescape(&line[start..end]).map(Some).ok_or_else(|| invalid("Invalid Escape")),
+        None => Ok(None)
+    }
+}
+
+
+#[test]
+pub fn test_extract_string() {
+    let text = |line:&str| get_text(line.to_string(), "text");
+    let line = r#"{"id": 1, "meta": {"text": "nested", "list": ["a\"}", {"b": 2}]}, "text": "line\none \"quoted\" caf\u00e9 \ud83d\ude00 😀 \\", "other": true}"#;
+    let expected:serde_json::Value = serde_json::from_str(line).unwrap();
+    assert_eq!(text(line).unwrap().as_deref(), expected["text"].as_str());
+    assert!(matches!(extract_string(r#"{"text":"plain"}"#, "text"), Ok(Some(Cow::Borrowed("plain")))));
+    assert_eq!(text(r#" {"id": 2, "text": "plain" } "#).unwrap().as_deref(), Some("plain"));
+    // Objects without a string value of the key are filtered
+    assert_eq!(text(r#"{"id":"text", "body":3}"#).unwrap(), None);
+    assert_eq!(text(r#"{"text":3}"#).unwrap(), None);
+    assert_eq!(text("{}").unwrap(), None);
+    // Lines which aren't valid are an error
+    for line in [r#"{"text":"unterminated}"#, "not json", r#"{"text" "value"}"#, r#"{"id":1 "text":"a"}"#, r#"{"text":"bad \q"}"#, ""] {
+        assert!(text(line).is_err(), "{}", line);
+    }
+}
@@ -1,16 +1,46 @@
 use std::{path::{PathBuf}, str::FromStr};
 
-use super::{ProviderChannel, gzip_file_provider, zstd_file_provider, provider_util::{get_download_type, DownloadType, is_network, get_local_path}, cache_writer, source_filter::SourceFilter, provider_config::{ProviderLength, Dataset}};
+use rand::seq::SliceRandom;
+
+use crate::{checkpoint::{DataCheckpoint, DataPosition}, random::{self, RandomStream}};
+
+use super::{shard::Shard, ProviderChannel, compressed_file_provider, text_file_provider, tar_file_provider, provider_util::{get_download_type, DownloadType, is_network, get_cache_name, expand_datasets}, cache_writer, source_filter::SourceFilter, provider_config::{ProviderLength, Dataset}};
 use tokio::sync::mpsc::Sender;
 
 
 
+// Order of the datasets in each epoch
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DatasetOrder {
+    pub seed:Option<u64>, // Shuffle the datasets for each epoch
+    pub shard:Option<Shard> // Datasets of the rank
+}
+
+impl DatasetOrder {
+    // Datasets of the rank for the epoch where the shared datasets are split by document
+    // All of the ranks shuffle with the same seed so the datasets are split without overlap
+    pub fn get_datasets(&self, datasets:&[Dataset], epoch:usize) -> Vec<(Dataset, bool)> {
+        let mut datasets = datasets.to_vec();
+        if let Some(seed) = self.seed {
+            datasets.shuffle(&mut random::create_rng(Some(seed), RandomStream::Files, epoch as u64));
+        }
+        match self.shard {
+            Some(x) => x.split_datasets(&datasets),
+            None => datasets.into_iter().map(|e| (e, false)).collect()
+        }
+    }
+}
 
 pub struct Counter {
     pub iterations:Option<usize>,
     pub epochs:Option<usize>,
     pub count:usize,
-    pub count_epoch:usize
+    pub count_epoch:usize,
+    pub skip:usize, // Documents to skip when resuming inside a dataset
+    pub stride:Option<Shard>, // Rank of the documents used from a dataset shared by all of the ranks
+    pub index:usize, // Documents read from the current dataset
+    pub invalid:usize, // Lines which weren't valid json
+    pub errors:usize // Sources which couldn't be read to the end
 }
 
 impl Counter {
@@ -25,8 +55,21 @@ impl Counter {
             iterations:iterations_,
             epochs:epochs_,
             count:0,
-            count_epoch:0
+            count_epoch:0,
+            skip:0,
+            stride:None,
+            index:0,
+            invalid:0,
+            errors:0
+        }
+    }
 
+    // Count a line which wasn't valid json. Piped and scraped input can contain blank or broken lines which are
+    // skipped. Only the first lines and then every thousandth line are logged
+    pub fn inc_invalid(&mut self, error:&serde_json::Error) {
+        self.invalid += 1;
+        if self.invalid <= 10 || self.invalid.is_multiple_of(1000) {
+            log::error!("Invalid Json Line {} : {}", self.invalid, error);
         }
     }
 
@@ -45,6 +88,29 @@ impl Counter {
         return false;
     }
 
+    // Start a dataset skipping the documents read before a checkpoint
+    pub fn start_dataset(&mut self, skip:usize, stride:Option<Shard>) {
+        self.skip = skip;
+        self.stride = stride;
+        self.index = 0;
+    }
+
+    // Returns true if the document should be skipped. Documents of other ranks are skipped along with the
+    // documents before a checkpoint which are already included in the count
+    pub fn skip_data(&mut self) -> bool {
+        self.index += 1;
+        if let Some(shard) = &self.stride {
+            if !shard.contains(self.index - 1) {
+                return true;
+            }
+        }
+        let skip = self.skip > 0;
+        if skip {
+            self.skip -= 1;
+        }
+        skip
+    }
+
     pub fn inc_epoch(&mut self) -> bool {
         self.count_epoch += 1;
         if self.iterations.is_some() && self.count == self.iterations.unwrap() {
@@ -66,17 +132,51 @@ pub async fn load_data_sets(datasets:Vec<Dataset>,
     length:ProviderLength, 
     tx:Sender<ProviderChannel<String>>, 
     cache:Option<String>,
-    filter:&SourceFilter) {
+    filter:&SourceFilter,
+    resume:Option<DataCheckpoint>,
+    order:DatasetOrder) {
 
 
     let mut counter = Counter::new(length);
 
+    // Continue from the dataset and document of the checkpoint
+    let mut start = resume.as_ref().and_then(|e| e.position.clone());
+    if let (Some(checkpoint), Some(position)) = (&resume, &start) {
+        log::info!("Resuming Data Provider at {:?}", position);
+        counter.count = checkpoint.documents;
+        counter.count_epoch = position.epoch;
+    }
+
+    // Directories and patterns are expanded once so every epoch uses the same files
+    let datasets = expand_datasets(datasets);
+    if datasets.is_empty() {
+        log::error!("No Datasets Found");
+        let _ = tx.send(ProviderChannel::Error("No Datasets Found".to_string())).await;
+        let _ = tx.send(ProviderChannel::Complete).await;
+        return;
+    }
+    if let Some(x) = order.shard {
+        log::info!("Data Shard {:?}", x);
+    }
+
     //log::info!("Sending Dataset Info");
     let _result = tx.send(ProviderChannel::Info(crate::tasks::DatasetInfo { name: "wiki".to_string(), length: 1000000000 })).await;
 
     loop {
 
-        for dataset in &datasets {
+        let epoch_datasets = order.get_datasets(&datasets, counter.count_epoch);
+        for (index, (dataset, shared)) in epoch_datasets.iter().enumerate() {
+            let offset = match start.take() {
+                Some(x) if x.dataset > index => {
+                    start = Some(x);
+                    continue;
+                },
+                Some(x) => x.offset,
+                None => 0
+            };
+            counter.start_dataset(offset, order.shard.filter(|_| *shared));
+            let _ = tx.send(ProviderChannel::Position(DataPosition { epoch: counter.count_epoch, dataset: index, offset })).await;
+
             // Download Type
             let typ = get_download_type(&dataset.location);
             
@@ -92,7 +192,7 @@ pub async fn load_data_sets(datasets:Vec<Dataset>,
                         if !cache_path.exists() {
                             log::error!("Cache Location : {:?} Doesn't Exist", cache_path);
                         }
-                        let base_file_path = cache_path.join(get_local_path(&dataset.location));
+                        let base_file_path = cache_path.join(get_cache_name(&dataset.location, filter));
                         let zstd_location = cache_writer::existing_cache_file(&base_file_path);
 
                         if zstd_location.is_some() {
@@ -101,8 +201,8 @@ pub async fn load_data_sets(datasets:Vec<Dataset>,
                         }
                         else {
                             log::info!("File Path {:?} {:?}", base_file_path, dataset.location);
-                            let writer = cache_writer::CacheWriter::new(base_file_path);
-                            (typ, None, Some(writer))        
+                            let writer = cache_writer::CacheWriter::new(base_file_path, &dataset.location);
+                            (typ, None, writer)        
                         }
                     },
                     None => (typ, None, None),
@@ -110,12 +210,27 @@ pub async fn load_data_sets(datasets:Vec<Dataset>,
             };
 
 
+            // Cached files hold the filtered text as {"text": ...} lines which are read without the configured filter
+            let cached = is_network(&dataset.location) && location.1.is_some();
+            let filter = if cached {&SourceFilter::FastText} else {filter};
+            let errors = counter.errors;
             match location {
-                (DownloadType::Zstd, None, z) => zstd_file_provider::load_url(dataset, &mut counter, &tx, z, filter).await,
-                (DownloadType::Zstd, Some(x), _) => zstd_file_provider::load_dataset(&x, &mut counter, &tx, filter).await,
-                (DownloadType::Gzip, None, z) => gzip_file_provider::load_url(dataset, &mut counter, &tx, z, filter).await,
-                (DownloadType::Gzip, Some(x), _) => gzip_file_provider::load_dataset(&x, &mut counter, &tx, filter).await,
-                (DownloadType::Error, _, _) => log::error!("Dataset Type Not Defined"),
+                // Tar archives which are optionally compressed
+                (typ, Some(x), _) if tar_file_provider::is_tar(&x.to_string_lossy()) => tar_file_provider::load_dataset(&x, typ, &mut counter, &tx, filter).await,
+                (typ, None, z) if tar_file_provider::is_tar(&dataset.location) => tar_file_provider::load_url(dataset, typ, &mut counter, &tx, z, filter).await,
+                (DownloadType::Json, Some(x), _) => text_file_provider::load_dataset(&x, &mut counter, &tx, filter).await,
+                (DownloadType::Text, Some(x), _) => text_file_provider::load_text_dataset(&x, &dataset.delimiter, &mut counter, &tx).await,
+                (DownloadType::Json, None, _) | (DownloadType::Text, None, _) => {
+                    log::error!("Uncompressed Datasets Only Supported Locally {}", dataset.location);
+                    counter.errors += 1;
+                },
+                // Compressed files where an unknown extension uses the magic bytes
+                (typ, None, z) => compressed_file_provider::load_url(dataset, typ, &mut counter, &tx, z, filter).await,
+                (typ, Some(x), _) => compressed_file_provider::load_dataset(&x, typ, &mut counter, &tx, filter).await,
+            }
+            // The documents read before the error are kept and the consumer decides if the output is usable
+            if counter.errors > errors {
+                let _ = tx.send(ProviderChannel::Error(dataset.location.clone())).await;
             }
 
             if counter.done() {
@@ -124,6 +239,10 @@ pub async fn load_data_sets(datasets:Vec<Dataset>,
                 return;
             }
         }
+        if let Some(x) = start.take() {
+            log::error!("Checkpoint Dataset {} Not Found. Starting from the Beginning", x.dataset);
+            continue;
+        }
         let finished = counter.inc_epoch();
         if finished {
             log::info!("Finished Data Provider");
@@ -135,3 +254,137 @@ pub async fn load_data_sets(datasets:Vec<Dataset>,
     
 
 }
+
+
+#[tokio::test]
+async fn test_resume_position() {
+    use super::test_util;
+
+    let dir = tempfile::tempdir().unwrap();
+    let first = test_util::write_documents(&dir.path().join("first.txt"), &["a 1", "a 2", "a 3"]);
+    let second = test_util::write_documents(&dir.path().join("second.txt"), &["b 1", "b 2"]);
+    let datasets:Vec<Dataset> = vec![first.as_str().into(), second.as_str().into()];
+
+    let load = |position:DataPosition, documents:usize| {
+        let datasets = datasets.clone();
+        let resume = DataCheckpoint { position: Some(position), documents, batches: 0, carry: vec![] };
+        test_util::run_provider(|tx| async move {
+            load_data_sets(datasets, ProviderLength::Epochs { epochs: 2 }, tx, None, &SourceFilter::JsonText, Some(resume), DatasetOrder::default()).await;
+        })
+    };
+
+    // Second document of the second dataset in the first epoch
+    let collected = load(DataPosition { epoch: 0, dataset: 1, offset: 1 }, 4).await;
+    assert_eq!(collected.data, vec!["b 2", "a 1", "a 2", "a 3", "b 1", "b 2"]);
+    assert_eq!(collected.positions[0], DataPosition { epoch: 0, dataset: 1, offset: 1 });
+    assert_eq!(collected.positions[1], DataPosition { epoch: 1, dataset: 0, offset: 0 });
+
+    let collected = load(DataPosition { epoch: 1, dataset: 0, offset: 2 }, 7).await;
+    assert_eq!(collected.data, vec!["a 3", "b 1", "b 2"]);
+}
+
+#[tokio::test]
+async fn test_dataset_expansion() {
+    use super::test_util;
+
+    let dir = tempfile::tempdir().unwrap();
+    for index in 0..8 {
+        test_util::write_documents(&dir.path().join(format!("part-{}.txt", index)), &[&format!("{} 1", index), &format!("{} 2", index)]);
+    }
+    std::fs::write(dir.path().join("part-0.manifest.json"), "{}").unwrap();
+    let location = dir.path().to_string_lossy().to_string();
+    let pattern = format!("{}/part-[0-3].txt", location);
+
+    let expanded = expand_datasets(vec![location.as_str().into(), pattern.as_str().into()]);
+    let names:Vec<String> = expanded.iter().map(|e| e.location.rsplit('/').next().unwrap().to_string()).collect();
+    assert_eq!(names[..8], (0..8).map(|e| format!("part-{}.txt", e)).collect::<Vec<String>>());
+    assert_eq!(names[8..], names[..4]);
+
+    // The order is shuffled for each epoch and repeated for the same seed
+    let order = DatasetOrder { seed: Some(3), shard: None };
+    let first:Vec<String> = order.get_datasets(&expanded[..8], 0).into_iter().map(|e| e.0.location).collect();
+    let second:Vec<String> = order.get_datasets(&expanded[..8], 1).into_iter().map(|e| e.0.location).collect();
+    assert_ne!(first, second);
+    assert_eq!(first, order.get_datasets(&expanded[..8], 0).into_iter().map(|e| e.0.location).collect::<Vec<String>>());
+
+    let values = test_util::run_provider(|tx| async move {
+        load_data_sets(vec![location.as_str().into()], ProviderLength::Epochs { epochs: 1 }, tx, None, &SourceFilter::JsonText, None, order).await;
+    }).await.data;
+    let expected:Vec<String> = first.iter().flat_map(|e| {
+        let index = &e[e.len() - 5..e.len() - 4];
+        vec![format!("{} 1", index), format!("{} 2", index)]
+    }).collect();
+    assert_eq!(values, expected);
+}
+
+#[tokio::test]
+async fn test_unequal_shards() {
+    use super::test_util;
+
+    // Files are split by count so ranks with files of unequal size read a different number of documents
+    let dir = tempfile::tempdir().unwrap();
+    for (index, lines) in [6, 2].iter().enumerate() {
+        let documents:Vec<String> = (0..*lines).map(|e| format!("{} {}", index, e)).collect();
+        test_util::write_json_lines(&dir.path().join(format!("part-{}.jsonl", index)), &documents);
+    }
+    let location = dir.path().to_string_lossy().to_string();
+
+    let load = |rank:usize, length:ProviderLength| {
+        let location = location.clone();
+        let order = DatasetOrder { seed: None, shard: Some(Shard::new(rank, 2)) };
+        async move {
+            test_util::run_provider(|tx| async move {
+                load_data_sets(vec![location.as_str().into()], length, tx, None, &SourceFilter::JsonText, None, order).await;
+            }).await.data
+        }
+    };
+
+    // The ranks are disjoint and cover the data but the epochs have a different length
+    let first = load(0, ProviderLength::Epochs { epochs: 1 }).await;
+    let second = load(1, ProviderLength::Epochs { epochs: 1 }).await;
+    assert_eq!((first.len(), second.len()), (6, 2));
+    assert!(first.iter().all(|e| e.starts_with('0')) && second.iter().all(|e| e.starts_with('1')));
+
+    // A length in iterations keeps the ranks aligned by repeating the smaller part
+    let first = load(0, ProviderLength::Iterations { iterations: 5 }).await;
+    let second = load(1, ProviderLength::Iterations { iterations: 5 }).await;
+    assert_eq!(first.len(), second.len());
+    assert_eq!(second, vec!["1 0", "1 1", "1 0", "1 1", "1 0"]);
+}
+
+#[tokio::test]
+async fn test_cached_json_filter() {
+    use super::source_filter::JsonFilter;
+
+    // The cache holds the text selected by the json filter which is read back without filtering again
+    let dir = tempfile::tempdir().unwrap();
+    let cache = dir.path().to_string_lossy().to_string();
+    let url = "http://localhost:1/data.json.gz".to_string();
+    let filter = SourceFilter::Json(JsonFilter::new(vec!["title", "/body/text"], vec![]));
+    let mut writer = cache_writer::CacheWriter::new(dir.path().join(get_cache_name(&url, &filter)), &url).unwrap();
+    writer.write_line("first\n\nbody".to_string());
+    writer.write_line("second".to_string());
+    assert!(writer.finish());
+    assert_ne!(get_cache_name(&url, &filter), get_cache_name(&url, &SourceFilter::JsonText));
+
+    let values = super::test_util::run_provider(|tx| async move {
+        load_data_sets(vec![url.as_str().into()], ProviderLength::Epochs { epochs: 1 }, tx, Some(cache), &filter, None, DatasetOrder::default()).await;
+    }).await.data;
+    assert_eq!(values, vec!["first\n\nbody", "second"]);
+}
+
+#[tokio::test]
+async fn test_empty_expansion() {
+    // A pattern which doesn't match any files completes without data instead of looping over no datasets
+    let dir = tempfile::tempdir().unwrap();
+    let pattern = format!("{}/part-*.txt", dir.path().to_string_lossy());
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<String>>(16);
+    let handle = tokio::spawn(async move {
+        load_data_sets(vec![pattern.as_str().into()], ProviderLength::Iterations { iterations: 10 }, tx, None, &SourceFilter::JsonText, None, DatasetOrder::default()).await;
+    });
+    let collected = super::test_util::collect_channel(rx).await;
+    assert!(collected.data.is_empty());
+    assert_eq!(collected.errors, vec!["No Datasets Found"]);
+    assert!(tokio::time::timeout(std::time::Duration::from_secs(10), handle).await.is_ok());
+}
@@ -0,0 +1,195 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
+
+use serde::Deserialize;
+
+use super::{arrow_provider::{ArrowDescriptor, ArrowFiles}, arrow_transfer::count_rows};
+
+
+// Resolves a HuggingFace dataset from an already populated datasets cache without using python
+// The cache layout is <root>/<dataset>/<config>/<version>/<hash>/ containing dataset_info.json
+// and the arrow shards <builder>-<split>.arrow or <builder>-<split>-00000-of-00004.arrow
+// Directories created with save_to_disk (dataset_dict.json/state.json) are also supported
+
+#[derive(Deserialize, Debug)]
+struct SplitInfo {
+    num_examples:Option<u32>
+}
+
+#[derive(Deserialize, Debug)]
+struct DatasetInfoFile {
+    builder_name:Option<String>,
+    splits:Option<HashMap<String, SplitInfo>>
+}
+
+#[derive(Deserialize, Debug)]
+struct DataFile {
+    filename:String
+}
+
+#[derive(Deserialize, Debug)]
+struct StateFile {
+    #[serde(rename = "_data_files")]
+    data_files:Vec<DataFile>,
+    #[serde(rename = "_split")]
+    split:Option<String>
+}
+
+#[derive(Deserialize, Debug)]
+struct DatasetDictFile {
+    splits:Vec<String>
+}
+
+// Location of the datasets cache following the HuggingFace environment variables
+pub fn get_cache_root() -> PathBuf {
+    if let Ok(x) = std::env::var("HF_DATASETS_CACHE") {
+        return PathBuf::from(x);
+    }
+    if let Ok(x) = std::env::var("HF_HOME") {
+        return PathBuf::from(x).join("datasets");
+    }
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".cache").join("huggingface").join("datasets")
+}
+
+// Find the dataset in the default cache location
+pub fn find_huggingface_dataset(dataset:&str, args:Option<&str>) -> Option<ArrowFiles> {
+    let dataset_path = Path::new(dataset);
+    if dataset_path.is_dir() {
+        return load_saved_dataset(dataset_path);
+    }
+    find_cached_dataset(&get_cache_root(), dataset, args)
+}
+
+// Find the dataset in the cache located at root
+pub fn find_cached_dataset(root:&Path, dataset:&str, args:Option<&str>) -> Option<ArrowFiles> {
+    let dataset_dir = root.join(dataset.replace('/', "___"));
+    let config_dir = match args {
+        Some(x) => dataset_dir.join(x),
+        None => {
+            let default = dataset_dir.join("default");
+            if default.is_dir() {
+                default
+            }
+            else {
+                // Datasets without a default use the builder config name (imdb -> plain_text)
+                sorted_dirs(&dataset_dir).into_iter().next()?
+            }
+        }
+    };
+
+    // Use the most recently written version/hash directory which contains the dataset information
+    let mut candidates = Vec::<PathBuf>::new();
+    for version in sorted_dirs(&config_dir) {
+        candidates.extend(sorted_dirs(&version).into_iter().filter(|e| e.join("dataset_info.json").exists()));
+    }
+    let hash_dir = candidates.into_iter().max_by_key(|e| fs::metadata(e.join("dataset_info.json")).and_then(|m| m.modified()).ok())?;
+    log::info!("Found HuggingFace Cache {:?}", hash_dir);
+    load_cache_dir(&hash_dir, dataset)
+}
+
+fn sorted_dirs(path:&Path) -> Vec<PathBuf> {
+    let mut dirs:Vec<PathBuf> = match fs::read_dir(path) {
+        Ok(x) => x.filter_map(|e| e.ok()).map(|e| e.path()).filter(|e| e.is_dir()).collect(),
+        Err(_) => vec![]
+    };
+    dirs.sort();
+    dirs
+}
+
+fn read_json<T:for<'de> Deserialize<'de>>(path:&Path) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(x) => Some(x),
+        Err(e) => {
+            log::error!("Can't Parse {:?} : {:?}", path, e);
+            None
+        }
+    }
+}
+
+// Returns the split a shard belongs to from the file name <builder>-<split>[-00000-of-00004].arrow
+fn get_split(filename:&str, builder:&str) -> Option<String> {
+    let base = filename.strip_suffix(".arrow")?.strip_prefix(builder)?.strip_prefix('-')?;
+    let parts:Vec<&str> = base.rsplitn(4, '-').collect();
+    let sharded = parts.len() == 4 && parts[1] == "of" && parts[0].chars().all(|c| c.is_ascii_digit()) && parts[2].chars().all(|c| c.is_ascii_digit());
+    if sharded {
+        Some(parts[3].to_string())
+    }
+    else {
+        Some(base.to_string())
+    }
+}
+
+fn load_cache_dir(path:&Path, dataset:&str) -> Option<ArrowFiles> {
+    let info:DatasetInfoFile = read_json(&path.join("dataset_info.json"))?;
+    let builder = info.builder_name.unwrap_or_else(|| dataset.rsplit('/').next().unwrap().to_string());
+
+    let mut files = HashMap::<String, Vec<String>>::new();
+    for entry in fs::read_dir(path).ok()?.filter_map(|e| e.ok()) {
+        let filename = entry.file_name().to_string_lossy().to_string();
+        if let Some(split) = get_split(&filename, &builder) {
+            files.entry(split).or_default().push(entry.path().to_string_lossy().to_string());
+        }
+    }
+
+    let split_info = info.splits.unwrap_or_default();
+    let mut children = Vec::<ArrowDescriptor>::with_capacity(files.len());
+    for (name, mut paths) in files {
+        paths.sort();
+        let num_rows = match split_info.get(&name).and_then(|e| e.num_examples) {
+            Some(x) => x,
+            None => count_rows(&paths)
+        };
+        children.push(ArrowDescriptor::new(name, paths, num_rows));
+    }
+    children.sort_by(|a, b| a.name.cmp(&b.name));
+    Some(ArrowFiles::new(children))
+}
+
+// Load a dataset written with save_to_disk which is either a single split or a dictionary of splits
+fn load_saved_dataset(path:&Path) -> Option<ArrowFiles> {
+    let splits = match read_json::<DatasetDictFile>(&path.join("dataset_dict.json")) {
+        Some(x) => x.splits.into_iter().map(|e| (Some(e.clone()), path.join(e))).collect(),
+        None => vec![(None, path.to_path_buf())]
+    };
+
+    let mut children = Vec::<ArrowDescriptor>::with_capacity(splits.len());
+    for (name, split_path) in splits {
+        let state:StateFile = read_json(&split_path.join("state.json"))?;
+        let paths:Vec<String> = state.data_files.iter().map(|e| split_path.join(&e.filename).to_string_lossy().to_string()).collect();
+        let name = name.or(state.split).unwrap_or_else(|| "train".to_string());
+        let num_rows = count_rows(&paths);
+        children.push(ArrowDescriptor::new(name, paths, num_rows));
+    }
+    Some(ArrowFiles::new(children))
+}
+
+
+#[test]
+pub fn test_cache_resolve() {
+    use std::{sync::Arc, fs::File};
+    use arrow::{array::StringArray, datatypes::{Schema, Field, DataType}, ipc::writer::StreamWriter, record_batch::RecordBatch};
+
+    let root = tempfile::tempdir().unwrap();
+    let hash_dir = root.path().join("user___data").join("default").join("0.0.0").join("abc123");
+    fs::create_dir_all(&hash_dir).unwrap();
+    fs::write(hash_dir.join("dataset_info.json"), r#"{"builder_name":"data","splits":{"train":{"name":"train","num_examples":6}}}"#).unwrap();
+
+    let schema = Arc::new(Schema::new(vec![Field::new("text", DataType::Utf8, false)]));
+    for (shard, split) in ["train-00000-of-00002", "train-00001-of-00002", "test"].iter().enumerate() {
+        let file = File::create(hash_dir.join(format!("data-{}.arrow", split))).unwrap();
+        let mut writer = StreamWriter::try_new(file, &schema).unwrap();
+        let text = StringArray::from(vec![format!("row {}", shard); 3]);
+        writer.write(&RecordBatch::try_new(schema.clone(), vec![Arc::new(text)]).unwrap()).unwrap();
+        writer.finish().unwrap();
+    }
+
+    let files = find_cached_dataset(root.path(), "user/data", None).unwrap();
+    let (paths, rows) = files.get_locations("train".to_string()).unwrap();
+    assert_eq!(paths.len(), 2);
+    assert!(paths[0].ends_with("data-train-00000-of-00002.arrow"));
+    assert_eq!(rows, 6);
+
+    let files = find_cached_dataset(root.path(), "user/data", None).unwrap();
+    assert_eq!(files.get_locations("test".to_string()).unwrap().1, 3);
+}
@@ -0,0 +1,215 @@
+use rand::{distributions::WeightedIndex, prelude::Distribution};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc::{Sender, Receiver}, task::JoinHandle};
+
+use crate::random::{self, RandomStream};
+
+use super::{ProviderChannel, general_file_provider::Counter, source_filter::SourceFilter, provider_config::{ProviderConfig, ProviderLength, SourceDescription}};
+
+
+// Weighted mixture of sources which interleaves documents from each component
+// The sampling probability of a component is proportional to weight^(1/temperature) so a temperature above 1
+// upsamples the smaller components. Each component runs as a separate provider and is restarted at the end of
+// every epoch until it reaches its epoch limit (the component epochs or the epochs of the mixture length)
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MixtureComponent {
+    pub source:SourceDescription,
+    pub weight:f64,
+    pub epochs:Option<usize>, // Maximum epochs of this component
+    pub filter:Option<SourceFilter> // Overrides the filter of the mixture
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MixtureDescription {
+    pub components:Vec<MixtureComponent>,
+    pub temperature:Option<f64>
+}
+
+// Function which starts the provider for a single component
+pub type ComponentProvider<T> = fn(ProviderConfig, Sender<ProviderChannel<T>>, Option<String>) -> JoinHandle<()>;
+
+struct ComponentState<T> {
+    config:ProviderConfig,
+    rx:Receiver<ProviderChannel<T>>,
+    epochs:Option<usize>,
+    epoch:usize,
+    count:usize, // Documents in the current epoch
+    active:bool
+}
+
+// Normalized sampling probabilities with the temperature applied
+pub fn get_probabilities(weights:&[f64], temperature:f64) -> Vec<f64> {
+    let total:f64 = weights.iter().sum();
+    let scaled:Vec<f64> = weights.iter().map(|e| (e/total).powf(1.0/temperature)).collect();
+    let total:f64 = scaled.iter().sum();
+    scaled.iter().map(|e| e/total).collect()
+}
+
+fn start_component<T>(config:&ProviderConfig, create:ComponentProvider<T>, cache:&Option<String>) -> Receiver<ProviderChannel<T>> {
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<T>>(256);
+    create(config.clone(), tx, cache.clone());
+    rx
+}
+
+fn create_index<T>(probabilities:&[f64], components:&[ComponentState<T>]) -> Option<WeightedIndex<f64>> {
+    let weights:Vec<f64> = probabilities.iter().zip(components).map(|(p, c)| if c.active {*p} else {0.0}).collect();
+    WeightedIndex::new(weights).ok()
+}
+
+pub async fn load_mixture<T:Send + 'static>(description:MixtureDescription,
+    config:ProviderConfig,
+    tx:Sender<ProviderChannel<T>>,
+    cache:Option<String>,
+    create:ComponentProvider<T>) {
+
+    // The mixture epochs are the default epoch limit for the components
+    let default_epochs = match config.length {
+        ProviderLength::Epochs { epochs } => Some(epochs),
+        ProviderLength::Iterations { iterations:_ } => None
+    };
+
+    let weights:Vec<f64> = description.components.iter().map(|e| e.weight).collect();
+    let probabilities = get_probabilities(&weights, description.temperature.unwrap_or(1.0));
+    log::info!("Mixture Probabilities {:?}", probabilities);
+
+    let mut components:Vec<ComponentState<T>> = description.components.into_iter().enumerate().map(|(index, e)| {
+        let component_config = ProviderConfig {
+            length: ProviderLength::Epochs { epochs: 1 },
+            source: e.source,
+            filter: e.filter.or_else(|| config.filter.clone()),
+            seed: config.seed.map(|s| random::derive_seed(s, RandomStream::Mixture, index as u64 + 1)),
+            resume: None,
+            ..config.clone()
+        };
+        let rx = start_component(&component_config, create, &cache);
+        ComponentState { config: component_config, rx, epochs: e.epochs.or(default_epochs), epoch: 0, count: 0, active: e.weight > 0.0 }
+    }).collect();
+
+    let _result = tx.send(ProviderChannel::Info(crate::tasks::DatasetInfo { name: "mixture".to_string(), length: 1000000000 })).await;
+
+    let mut counter = Counter::new(config.length);
+    let mut index = create_index(&probabilities, &components);
+    // The components are sampled with a seed of 0 without a seed so a resume skips the documents of the same order
+    let mut rng = random::create_rng(Some(config.seed.unwrap_or(0)), RandomStream::Mixture, 0);
+    while let Some(position) = index.as_ref().map(|e| e.sample(&mut rng)) {
+        let component = &mut components[position];
+        match component.rx.recv().await {
+            Some(ProviderChannel::Data(x)) => {
+                component.count += 1;
+                // The receiver is closed when the training stops
+                if tx.send(ProviderChannel::Data(x)).await.is_err() {
+                    break;
+                }
+                if counter.inc_data() {
+                    break;
+                }
+            },
+            // The positions of the components can't be resumed after the mixing
+            Some(ProviderChannel::Info(_)) | Some(ProviderChannel::Position(_)) | Some(ProviderChannel::Checkpoint(_)) => {},
+            Some(ProviderChannel::Error(x)) => {
+                let _ = tx.send(ProviderChannel::Error(x)).await;
+            },
+            Some(ProviderChannel::Complete) | None => {
+                component.epoch += 1;
+                log::info!("Mixture Component {} Finished Epoch {} with {} Documents", position, component.epoch, component.count);
+                let finished = component.epochs.map(|e| component.epoch >= e).unwrap_or(false);
+                if component.count == 0 || finished {
+                    if component.count == 0 {
+                        log::error!("Mixture Component {} has no Data", position);
+                    }
+                    component.active = false;
+                    index = create_index(&probabilities, &components);
+                }
+                else {
+                    // New shuffle seed for each epoch of the component which starts from 0 without a seed
+                    let mut epoch_config = component.config.clone();
+                    epoch_config.seed = Some(random::derive_seed(component.config.seed.unwrap_or(0), RandomStream::Shuffle, component.epoch as u64));
+                    component.count = 0;
+                    component.rx = start_component(&epoch_config, create, &cache);
+                }
+            }
+        }
+    }
+    let epochs:Vec<usize> = components.iter().map(|e| e.epoch).collect();
+    log::info!("Finished Mixture Provider : Component Epochs {:?}", epochs);
+    let _ = tx.send(ProviderChannel::Complete).await;
+}
+
+
+#[test]
+pub fn test_temperature() {
+    let probabilities = get_probabilities(&[9.0, 1.0], 1.0);
+    assert!((probabilities[0] - 0.9).abs() < 1e-9);
+    // Temperature 2 uses the square root of the weights
+    let probabilities = get_probabilities(&[9.0, 1.0], 2.0);
+    assert!((probabilities[0] - 0.75).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_mixture_epochs() {
+    use crate::tasks::masking::masking_runner::create_provider;
+    use super::test_util;
+
+    let dir = tempfile::tempdir().unwrap();
+    let first = test_util::write_documents(&dir.path().join("first.txt"), &["a 1", "a 2", "a 3"]);
+    let second = test_util::write_documents(&dir.path().join("second.txt"), &["b 1"]);
+
+    let component = |path:&str, epochs:Option<usize>| MixtureComponent {
+        source: SourceDescription::DataList(vec![path.into()]), weight: 1.0, epochs, filter: None
+    };
+    let description = MixtureDescription { components: vec![component(&first, None), component(&second, Some(3))], temperature: Some(2.0) };
+    let config = ProviderConfig::new(ProviderLength::Epochs { epochs: 2 }, SourceDescription::Mixture(description));
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<String>>(64);
+    create_provider(config, tx, None);
+    let values = test_util::collect_channel(rx).await.data;
+    // Two epochs of the first component and the three epochs configured for the second
+    assert_eq!(values.iter().filter(|e| e.starts_with('a')).count(), 6);
+    assert_eq!(values.iter().filter(|e| e.starts_with('b')).count(), 3);
+}
+
+#[tokio::test]
+async fn test_mixture_closed() {
+    use crate::tasks::masking::masking_runner::create_provider;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = super::test_util::write_documents(&dir.path().join("data.txt"), &["a 1", "a 2"]);
+
+    // Without a limit on the epochs the provider only stops when the receiver is closed
+    let component = MixtureComponent { source: SourceDescription::DataList(vec![path.as_str().into()]), weight: 1.0, epochs: None, filter: None };
+    let description = MixtureDescription { components: vec![component], temperature: None };
+    let config = ProviderConfig::new(ProviderLength::Iterations { iterations: 1000000000 }, SourceDescription::Mixture(description));
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<ProviderChannel<String>>(4);
+    let handle = create_provider(config, tx, None);
+    while let Some(data) = rx.recv().await {
+        if let ProviderChannel::Data(_) = data {
+            break;
+        }
+    }
+    drop(rx);
+    assert!(tokio::time::timeout(std::time::Duration::from_secs(10), handle).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_mixture_unseeded_epochs() {
+    use crate::tasks::masking::masking_runner::create_provider;
+
+    let dir = tempfile::tempdir().unwrap();
+    for index in 0..8 {
+        super::test_util::write_documents(&dir.path().join(format!("part-{}.txt", index)), &[&index.to_string()]);
+    }
+
+    // The files of a shuffled component without a seed are in a new order for each epoch
+    let component = MixtureComponent { source: SourceDescription::DataList(vec![dir.path().to_str().unwrap().into()]), weight: 1.0, epochs: None, filter: None };
+    let description = MixtureDescription { components: vec![component], temperature: None };
+    let config = ProviderConfig { shuffle: Some(true), ..ProviderConfig::new(ProviderLength::Epochs { epochs: 3 }, SourceDescription::Mixture(description)) };
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<String>>(64);
+    create_provider(config, tx, None);
+    let values = super::test_util::collect_channel(rx).await.data;
+    assert_eq!(values.len(), 24);
+    let epochs:Vec<&[String]> = values.chunks(8).collect();
+    assert!(epochs[1] != epochs[0] || epochs[2] != epochs[1]);
+}
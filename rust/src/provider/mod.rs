@@ -1,6 +1,6 @@
 
 
-use crate::tasks::DatasetInfo;
+use crate::{tasks::{DatasetInfo, squad::squad_data::SquadGeneral}, checkpoint::{DataPosition, DataCheckpoint}, models::simple_transport::SimpleTransport};
 
 
 
@@ -9,19 +9,72 @@ pub mod provider_util;
 
 pub mod arrow_provider;
 pub mod arrow_transfer;
+pub mod hugging_cache;
+pub mod parquet_provider;
+pub mod column_generator;
 
 pub mod pile_datasets;
 pub mod general_file_provider;
-pub mod gzip_file_provider;
-pub mod zstd_file_provider;
+pub mod mixture_provider;
+pub mod shuffle_buffer;
+pub mod dedup;
+pub mod quality_filter;
+pub mod shard;
+pub mod url_reader;
+pub mod compressed_file_provider;
+pub mod text_file_provider;
+pub mod tar_file_provider;
+pub mod stream_provider;
 
 pub mod cache_writer;
+pub mod cache_manager;
+pub mod corpus_writer;
 pub mod source_filter;
+pub mod fast_text;
+#[cfg(test)]
+pub mod test_util;
 
 pub enum ProviderChannel<T> {
     Complete,
     Info(DatasetInfo),
-    Data(T)
+    Data(T),
+    Position(DataPosition), // Position of the provider sent before the data of a dataset or epoch
+    Checkpoint(DataCheckpoint), // Checkpoint from the batcher valid once the next batch is delivered
+    Error(String) // Source which couldn't be read to the end
 }
 
+// Text of the provider items used by the filtering stages. Items without text are passed through
+pub trait DocumentText {
+    fn document_text(&self) -> Option<&str>;
+    fn set_document_text(&mut self, text:String);
+}
+
+impl DocumentText for String {
+    fn document_text(&self) -> Option<&str> {
+        Some(self)
+    }
+
+    fn set_document_text(&mut self, text:String) {
+        *self = text;
+    }
+}
+
+impl DocumentText for SquadGeneral {
+    fn document_text(&self) -> Option<&str> {
+        Some(&self.context)
+    }
 
+    fn set_document_text(&mut self, text:String) {
+        self.context = text;
+    }
+}
+
+impl DocumentText for SimpleTransport {
+    fn document_text(&self) -> Option<&str> {
+        Some(&self.data.text)
+    }
+
+    fn set_document_text(&mut self, text:String) {
+        self.data.text = text;
+    }
+}
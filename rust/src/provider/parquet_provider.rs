@@ -0,0 +1,151 @@
+use std::{fs::File, io::Read, ops::Range, sync::Arc};
+
+use arrow::{datatypes::Schema, record_batch::RecordBatch};
+use bytes::Bytes;
+use futures::{future::BoxFuture, FutureExt, StreamExt};
+use parquet::{arrow::{arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder}, async_reader::{AsyncFileReader, ParquetRecordBatchStreamBuilder}},
+    errors::Result as ParquetResult, file::metadata::ParquetMetaData};
+
+
+// Parquet files are decoded into arrow record batches and loaded through the ArrowTransfer
+// Each row group can be read independently which allows shuffling without loading the full file
+
+// Parquet files start with the magic bytes PAR1
+pub fn is_parquet(location:&str) -> bool {
+    let mut magic = [0u8;4];
+    let result = File::open(location).and_then(|mut f| f.read_exact(&mut magic));
+    result.is_ok() && &magic == b"PAR1"
+}
+
+fn create_builder(location:&str) -> Option<ParquetRecordBatchReaderBuilder<File>> {
+    let f = File::open(location).ok()?;
+    match ParquetRecordBatchReaderBuilder::try_new(f) {
+        Ok(x) => Some(x),
+        Err(e) => {
+            log::error!("Can't Read Parquet File {} {:?}", location, e);
+            None
+        }
+    }
+}
+
+// Reader over all of the row groups in the file
+pub fn create_parquet_reader(location:&str) -> Option<(Arc<Schema>, ParquetRecordBatchReader)> {
+    let builder = create_builder(location)?;
+    let schema = builder.schema().clone();
+    builder.build().ok().map(|e| (schema, e))
+}
+
+// Number of rows from the parquet metadata
+pub fn get_parquet_rows(location:&str) -> u32 {
+    create_builder(location).map(|e| e.metadata().file_metadata().num_rows() as u32).unwrap_or(0)
+}
+
+pub fn get_row_groups(location:&str) -> usize {
+    create_builder(location).map(|e| e.metadata().num_row_groups()).unwrap_or(0)
+}
+
+// Reader of a row group which uses the metadata of the ParquetFile instead of parsing the footer again
+struct RowGroupReader {
+    file:tokio::fs::File,
+    metadata:Arc<ParquetMetaData>
+}
+
+impl AsyncFileReader for RowGroupReader {
+    fn get_bytes(&mut self, range:Range<usize>) -> BoxFuture<'_, ParquetResult<Bytes>> {
+        self.file.get_bytes(range)
+    }
+
+    fn get_metadata(&mut self) -> BoxFuture<'_, ParquetResult<Arc<ParquetMetaData>>> {
+        let metadata = self.metadata.clone();
+        async move {Ok(metadata)}.boxed()
+    }
+}
+
+// Parquet file with random access to the row groups. The metadata is parsed once when the file is opened
+pub struct ParquetFile {
+    location:String,
+    metadata:Arc<ParquetMetaData>
+}
+
+impl ParquetFile {
+    pub fn open(location:&str) -> Option<Self> {
+        let builder = create_builder(location)?;
+        Some(Self { location: location.to_string(), metadata: builder.metadata().clone() })
+    }
+
+    pub fn num_row_groups(&self) -> usize {
+        self.metadata.num_row_groups()
+    }
+
+    // Read a single row group from the file which is opened again for the reader of the row group
+    pub async fn read_row_group(&self, index:usize) -> Vec<RecordBatch> {
+        let file = match tokio::fs::File::open(&self.location).await {
+            Ok(x) => x,
+            Err(e) => {
+                log::error!("Can't Open Parquet File {} {:?}", self.location, e);
+                return vec![]
+            }
+        };
+        let reader = RowGroupReader { file, metadata: self.metadata.clone() };
+        let stream = ParquetRecordBatchStreamBuilder::new(reader).await.and_then(|e| e.with_row_groups(vec![index]).build());
+        match stream {
+            Ok(x) => x.filter_map(|e| async move {e.ok()}).collect().await,
+            Err(e) => {
+                log::error!("Can't Read Row Group {} of {} {:?}", index, self.location, e);
+                vec![]
+            }
+        }
+    }
+}
+
+
+#[tokio::test]
+async fn test_parquet_row_groups() {
+    use arrow::{array::StringArray, datatypes::{Field, DataType}};
+    use parquet::{arrow::ArrowWriter, file::properties::WriterProperties};
+    use crate::provider::{test_util, arrow_transfer::ArrowTransfer, column_generator::TextArrowGenerator, provider_config::{ProviderConfig, ProviderLength, SourceDescription}};
+
+    let dir = tempfile::tempdir().unwrap();
+    let location = dir.path().join("data.parquet").to_string_lossy().to_string();
+    let schema = Arc::new(Schema::new(vec![Field::new("text", DataType::Utf8, false)]));
+    let properties = WriterProperties::builder().set_max_row_group_size(4).build();
+    let mut writer = ArrowWriter::try_new(File::create(&location).unwrap(), schema.clone(), Some(properties)).unwrap();
+    let text = StringArray::from((0..10).map(|e| format!("row {}", e)).collect::<Vec<String>>());
+    writer.write(&RecordBatch::try_new(schema, vec![Arc::new(text)]).unwrap()).unwrap();
+    writer.close().unwrap();
+
+    assert!(is_parquet(&location));
+    assert_eq!(get_row_groups(&location), 3);
+
+    let mut loader = ArrowTransfer::<String>::from_local(dir.path().to_str().unwrap(), ".parquet").unwrap();
+    assert_eq!(loader.num_rows, 10);
+    loader.generator = Some(Box::new(TextArrowGenerator::new(&loader.schema, "text").unwrap()));
+    let config = ProviderConfig { shuffle: Some(true), flatten: Some(false), seed: Some(3),
+        ..ProviderConfig::new(ProviderLength::Iterations { iterations: 20 }, SourceDescription::Parquet(location.clone())) };
+
+    let values = test_util::run_provider(|tx| async move {loader.load_data(config, tx).await}).await.data;
+    // Two epochs over every row of the row groups
+    assert_eq!(values.len(), 20);
+    let mut orders = Vec::<Vec<usize>>::new();
+    for epoch in values.chunks(10) {
+        let rows:Vec<usize> = epoch.iter().map(|e| e[4..].parse().unwrap()).collect();
+        let mut sorted = rows.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..10).collect::<Vec<usize>>());
+        // The rows of a row group are kept together
+        let mut order:Vec<usize> = rows.iter().map(|e| e / 4).collect();
+        order.dedup();
+        assert_eq!(order.len(), 3);
+        orders.push(order);
+    }
+    // The shuffle changes the order of the row groups
+    assert!(orders.iter().any(|e| *e != vec![0, 1, 2]));
+    assert_ne!(orders[0], orders[1]);
+
+    let file = ParquetFile::open(&location).unwrap();
+    let mut groups = Vec::<usize>::new();
+    for index in 0..file.num_row_groups() {
+        groups.push(file.read_row_group(index).await.iter().map(|e| e.num_rows()).sum());
+    }
+    assert_eq!(groups, vec![4, 4, 2]);
+}
@@ -64,7 +64,8 @@ fn get_internal_zstd(location:String, exists:bool) -> Option<Vec<Dataset>> {
         None
     } else {
         let dataset = Dataset{
-            location: location
+            location: location,
+            delimiter: None
         };
         Some(vec!(dataset))
     }
@@ -81,6 +82,7 @@ pub fn get_datasets(typ:PileDatasetType) -> Option<Vec<Dataset>> {
                                else {format!("https://mystic.the-eye.eu/public/AI/pile/train/{}.jsonl.zst",x)};
                 let dataset = Dataset{
                     location: location,
+                    delimiter: None
                 };
                 result.push(dataset);
             }
@@ -88,25 +90,25 @@ pub fn get_datasets(typ:PileDatasetType) -> Option<Vec<Dataset>> {
             
         },
         PileDatasetType::Wiki => {
-            let _dataset = Dataset{
+            let dataset = Dataset{
                 location: "http://eaidata.bmk.sh/data/wikipedia-en.tar.gz".to_string(),
+                delimiter: None
             };
-            None
-            //vec!(dataset)
+            Some(vec!(dataset))
         },
         PileDatasetType::OpensubtitlesDataset => {
-            let _dataset = Dataset{
+            let dataset = Dataset{
                 location: "http://eaidata.bmk.sh/data/opensubtitles_out.tar".to_string(),
+                delimiter: None
             };
-            None
-            //vec!(dataset)
+            Some(vec!(dataset))
         },
         PileDatasetType::BookCorpus => {
-            let _dataset = Dataset{
+            let dataset = Dataset{
                 location: "https://the-eye.eu/public/AI/pile_preliminary_components/books1.tar.gz".to_string(),
+                delimiter: None
             };
-            None
-            //vec!(dataset)
+            Some(vec!(dataset))
         },
         PileDatasetType::Enron => {
             get_internal_zstd("http://eaidata.bmk.sh/data/enron_emails.jsonl.zst".to_string(), true)
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use super::{pile_datasets::PileDatasetType, source_filter::SourceFilter};
+use crate::checkpoint::DataCheckpoint;
+
+use super::{shard::Shard, general_file_provider::DatasetOrder, pile_datasets::PileDatasetType, source_filter::SourceFilter, mixture_provider::MixtureDescription, shuffle_buffer::{ShuffleBufferConfig, DEFAULT_BUFFER_SIZE}, dedup::DedupConfig, quality_filter::QualityConfig};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub enum ProviderLength {
@@ -25,6 +27,11 @@ impl HuggingDescription {
             operations: o.into_iter().map(|s|s.to_string()).collect(),
         }
     }
+
+    // Split of the dataset which is the first operation or the train split without operations
+    pub fn get_split(&self) -> String {
+        self.operations.first().cloned().unwrap_or_else(|| "train".to_string())
+    }
 }
 
 
@@ -44,12 +51,15 @@ pub struct PileFullDescription {
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Dataset {
-    pub location:String,    
+    pub location:String,
+    // Line which separates documents in plain text files. Defaults to a blank line
+    #[serde(default)]
+    pub delimiter:Option<String>
 }
 
 impl From<&str> for Dataset {
     fn from(x: &str) -> Self {
-        Dataset{location:x.to_string()}
+        Dataset{location:x.to_string(), delimiter:None}
     }
 }
 
@@ -68,18 +78,120 @@ pub enum SourceDescription {
     Pile{typ:PileDatasetType},
     #[serde(rename = "arrow")]
     Arrow(String),
+    #[serde(rename = "parquet")]
+    Parquet(String),
     #[serde(rename="list")]
-    DataList(Vec<Dataset>)
+    DataList(Vec<Dataset>),
+    #[serde(rename="mixture")]
+    Mixture(MixtureDescription),
+    #[serde(rename="stdin")]
+    Stdin,
+    #[serde(rename="fifo")]
+    Fifo(String) // Named pipe
 
 }
 
+// Type of the label column. Single is an integer class and Multi is a list of integer classes
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub enum LabelType {
+    #[serde(rename = "single")]
+    Single,
+    #[serde(rename = "multi")]
+    Multi
+}
+
+// Names of the columns used from an arrow or parquet dataset
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ColumnConfig {
+    pub text:String,
+    pub pair:Option<String>, // Optional second text input
+    pub label:Option<String>,
+    pub label_type:Option<LabelType> // Taken from the schema if not specified
+}
+
+impl ColumnConfig {
+    pub fn new(text:&str, pair:Option<&str>, label:Option<&str>, label_type:Option<LabelType>) -> Self {
+        Self {
+            text: text.to_string(),
+            pair: pair.map(|s|s.to_string()),
+            label: label.map(|s|s.to_string()),
+            label_type
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ProviderConfig {
     pub shuffle:Option<bool>, // Shuffle the data
     pub flatten:Option<bool>, // Load all the data into memory
     pub length:ProviderLength,
     pub source:SourceDescription,
-    pub filter:Option<SourceFilter>
+    pub filter:Option<SourceFilter>,
+    #[serde(default)]
+    pub columns:Option<ColumnConfig>, // Column mapping for arrow and parquet sources
+    #[serde(default)]
+    pub shuffle_buffer:Option<ShuffleBufferConfig>, // Shuffle buffer between the provider and the batcher
+    #[serde(default)]
+    pub seed:Option<u64>, // Seed for shuffling. Set from the training seed if it exists
+    #[serde(default)]
+    pub resume:Option<DataCheckpoint>, // Checkpoint returned by the transport to continue the data stream
+    #[serde(default)]
+    pub rank:Option<usize>, // Rank of the trainer for distributed training
+    #[serde(default)]
+    pub world_size:Option<usize>, // Number of trainers which share the data
+    #[serde(default)]
+    pub dedup:Option<DedupConfig>, // Near duplicate filtering between the provider and the batcher
+    #[serde(default)]
+    pub quality:Option<QualityConfig> // Heuristic quality filters applied before the dedup
+}
+
+impl ProviderConfig {
+    // Configuration with the defaults for the optional settings
+    pub fn new(length:ProviderLength, source:SourceDescription) -> Self {
+        Self {
+            shuffle: None,
+            flatten: None,
+            length,
+            source,
+            filter: None,
+            columns: None,
+            shuffle_buffer: None,
+            seed: None,
+            resume: None,
+            rank: None,
+            world_size: None,
+            dedup: None,
+            quality: None
+        }
+    }
+
+    // Streamed line based sources use a default shuffle buffer when shuffle is set
+    pub fn get_shuffle_buffer(&self) -> Option<ShuffleBufferConfig> {
+        if let Some(x) = &self.shuffle_buffer {
+            return Some(ShuffleBufferConfig::new(x.size, x.seed.or(self.seed)));
+        }
+        let streamed = matches!(self.source, SourceDescription::DataList(_) | SourceDescription::Pile{..} | SourceDescription::Mixture(_) | SourceDescription::Stdin | SourceDescription::Fifo(_));
+        if streamed && self.shuffle.unwrap_or(false) {
+            Some(ShuffleBufferConfig::new(DEFAULT_BUFFER_SIZE, self.seed))
+        }
+        else {
+            None
+        }
+    }
+
+    // Part of the data for the rank. The full data is used without a world size larger than 1
+    pub fn get_shard(&self) -> Option<Shard> {
+        match self.world_size {
+            Some(x) if x > 1 => Some(Shard::new(self.rank.unwrap_or(0), x)),
+            _ => None
+        }
+    }
+
+    // Shuffled data lists use a seed of 0 without a seed so the order is the same on every rank and for a resume
+    pub fn get_dataset_order(&self) -> DatasetOrder {
+        let seed = if self.shuffle.unwrap_or(false) {Some(self.seed.unwrap_or(0))} else {None};
+        DatasetOrder { seed, shard: self.get_shard() }
+    }
 }
 
 pub enum Examples {
@@ -94,49 +206,34 @@ impl Examples {
     pub fn get_config(&self, test:bool) -> ProviderConfig {
         if test {
             match self {
-                Examples::Mask => ProviderConfig {
-                    shuffle: None,
-                    flatten: None,
-                    length: ProviderLength::Iterations { iterations: 10 },
-                    source: SourceDescription::DataList(vec![Dataset{location:"../data/test.json.gz".to_string()}]),
-                    filter: None,
-                },
+                Examples::Mask => ProviderConfig::new(ProviderLength::Iterations { iterations: 10 }, SourceDescription::DataList(vec![Dataset{location:"../data/test.json.gz".to_string(), delimiter:None}])),
                 Examples::Squad => {
                     ProviderConfig {
                         shuffle: Some(true),
                         flatten: Some(true),
-                        length: ProviderLength::Iterations { iterations:1024 },
-                        source: SourceDescription::HuggingFace(HuggingDescription { dataset:"squad".to_string(), args: None, operations: vec![] }),
-                        filter: None,
+                        ..ProviderConfig::new(ProviderLength::Iterations { iterations:1024 }, SourceDescription::HuggingFace(HuggingDescription { dataset:"squad".to_string(), args: None, operations: vec!["train".to_string()] }))
                     }
                 },
                 Examples::Emot => {
                     ProviderConfig {
                         shuffle: Some(true),
                         flatten: Some(true),
-                        length: ProviderLength::Iterations { iterations:1024 },
-                        source: SourceDescription::HuggingFace(HuggingDescription { dataset:"xed_en_fi".to_string(), 
-                            args: Some("en_annotated".to_string()), operations: vec!["train".to_string()] }),
-                        filter: None,
+                        ..ProviderConfig::new(ProviderLength::Iterations { iterations:1024 }, SourceDescription::HuggingFace(HuggingDescription { dataset:"xed_en_fi".to_string(), args: Some("en_annotated".to_string()), operations: vec!["train".to_string()] }))
                     }
                 },
                 Examples::Imdb => {
                     ProviderConfig {
                         shuffle: Some(true),
                         flatten: Some(true),
-                        length: ProviderLength::Iterations { iterations:1024 },
-                        source: SourceDescription::HuggingFace(HuggingDescription { dataset:"imdb".to_string(), 
-                            args: None, operations: vec!["train".to_string()]}),
-                        filter: None,
+                        ..ProviderConfig::new(ProviderLength::Iterations { iterations:1024 }, SourceDescription::HuggingFace(HuggingDescription { dataset:"imdb".to_string(), args: None, operations: vec!["train".to_string()]}))
                     }
                 },
                 Examples::Python => {
                     ProviderConfig {
                         shuffle: Some(true),
                         flatten: Some(true),
-                        length: ProviderLength::Iterations { iterations:32 },
-                        source: SourceDescription::Pile { typ:PileDatasetType::GithubDataset },
                         filter: Some(SourceFilter::PythonText),
+                        ..ProviderConfig::new(ProviderLength::Iterations { iterations:32 }, SourceDescription::Pile { typ:PileDatasetType::GithubDataset })
                     }
                 },
                 
@@ -144,49 +241,34 @@ impl Examples {
         }
         else {
             match self {
-                Examples::Mask => ProviderConfig {
-                    shuffle: None,
-                    flatten: None,
-                    length: ProviderLength::Epochs { epochs : 1 },
-                    source: SourceDescription::Pile { typ:PileDatasetType::Total },
-                    filter: None,
-                },
+                Examples::Mask => ProviderConfig::new(ProviderLength::Epochs { epochs : 1 }, SourceDescription::Pile { typ:PileDatasetType::Total }),
                 Examples::Squad => {
                     ProviderConfig {
                         shuffle: Some(true),
                         flatten: Some(true),
-                        length: ProviderLength::Epochs { epochs:3 },
-                        source: SourceDescription::HuggingFace(HuggingDescription { dataset:"squad".to_string(), args: None, operations: vec![] }),
-                        filter: None,
+                        ..ProviderConfig::new(ProviderLength::Epochs { epochs:3 }, SourceDescription::HuggingFace(HuggingDescription { dataset:"squad".to_string(), args: None, operations: vec!["train".to_string()] }))
                     }
                 },
                 Examples::Emot => {
                     ProviderConfig {
                         shuffle: Some(true),
                         flatten: Some(true),
-                        length: ProviderLength::Epochs { epochs:3 },
-                        source: SourceDescription::HuggingFace(HuggingDescription { dataset:"xed_en_fi".to_string(), 
-                            args: Some("en_annotated".to_string()), operations: vec!["train".to_string()] }),
-                        filter: None,
+                        ..ProviderConfig::new(ProviderLength::Epochs { epochs:3 }, SourceDescription::HuggingFace(HuggingDescription { dataset:"xed_en_fi".to_string(), args: Some("en_annotated".to_string()), operations: vec!["train".to_string()] }))
                     }
                 },
                 Examples::Imdb => {
                     ProviderConfig {
                         shuffle: Some(true),
                         flatten: Some(true),
-                        length: ProviderLength::Epochs { epochs:3 },
-                        source: SourceDescription::HuggingFace(HuggingDescription { dataset:"imdb".to_string(), 
-                            args: None, operations: vec!["train".to_string()]}),
-                        filter: None,
+                        ..ProviderConfig::new(ProviderLength::Epochs { epochs:3 }, SourceDescription::HuggingFace(HuggingDescription { dataset:"imdb".to_string(), args: None, operations: vec!["train".to_string()]}))
                     }
                 },
                 Examples::Python => {
                     ProviderConfig {
                         shuffle: Some(true),
                         flatten: Some(true),
-                        length: ProviderLength::Epochs { epochs:1 },
-                        source: SourceDescription::Pile { typ:PileDatasetType::GithubDataset },
                         filter: Some(SourceFilter::PythonText),
+                        ..ProviderConfig::new(ProviderLength::Epochs { epochs:1 }, SourceDescription::Pile { typ:PileDatasetType::GithubDataset })
                     }
                 },
                
@@ -1,16 +1,41 @@
+use std::path::Path;
+
 use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::{provider_config::Dataset, source_filter::SourceFilter};
 
 #[derive(Debug)]
 pub enum DownloadType {
     Zstd,
     Gzip,
+    Xz,
+    Bzip2,
+    Lz4,
+    Json,
+    Text,
     Error
 }
 
 
+// Name of the cache files for a url. The mangled url is readable but lossy so a hash of the url keeps the names unique
 pub fn get_local_path(path:&String) -> String {
-    path.replace("http", "").replace(":", "").replace("/", "_").replace(".", "_")
-    
+    let hash:String = Sha256::digest(path.as_bytes()).iter().take(6).map(|e| format!("{:02x}", e)).collect();
+    let name = path.replace("http", "").replace(":", "").replace("/", "_").replace(".", "_");
+    format!("{}_{}", name, hash)
+}
+
+// Name of the cache files for a url read with the filter. The cache holds the filtered text so a filter which
+// selects something other than the text field has separate cache files
+pub fn get_cache_name(path:&String, filter:&SourceFilter) -> String {
+    match filter {
+        SourceFilter::JsonText | SourceFilter::FastText => get_local_path(path),
+        _ => {
+            let description = serde_json::to_string(filter).unwrap_or_default();
+            let hash:String = Sha256::digest(description.as_bytes()).iter().take(4).map(|e| format!("{:02x}", e)).collect();
+            format!("{}_{}", get_local_path(path), hash)
+        }
+    }
 }
 
 pub fn is_network(path:&String) -> bool {
@@ -18,11 +43,26 @@ pub fn is_network(path:&String) -> bool {
 }
 
 pub fn get_download_type(path:&String) -> DownloadType{
-    if &path[path.len()-4..path.len()] == ".zst" {
-        return DownloadType::Zstd
+    if path.ends_with(".zst") {
+        DownloadType::Zstd
     }   
-    else if &path[path.len()-3..path.len()] == ".gz"{
-        return DownloadType::Gzip
+    else if path.ends_with(".gz") {
+        DownloadType::Gzip
+    }
+    else if path.ends_with(".xz") {
+        DownloadType::Xz
+    }
+    else if path.ends_with(".bz2") {
+        DownloadType::Bzip2
+    }
+    else if path.ends_with(".lz4") {
+        DownloadType::Lz4
+    }
+    else if path.ends_with(".jsonl") || path.ends_with(".json") {
+        DownloadType::Json
+    }
+    else if path.ends_with(".txt") {
+        DownloadType::Text
     }
     else {
         DownloadType::Error
@@ -30,6 +70,59 @@ pub fn get_download_type(path:&String) -> DownloadType{
 }
 
 
+// Expands a local location which is either a file, a directory or a glob pattern into a sorted list of files
+// Files in a directory are filtered by the extension if one is supplied
+pub fn expand_local_path(location:&str, extension:Option<&str>) -> Vec<String> {
+    let path = Path::new(location);
+    let mut result:Vec<String> = if path.is_file() {
+        vec![location.to_string()]
+    }
+    else if path.is_dir() {
+        match std::fs::read_dir(path) {
+            Ok(x) => x.filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|e| e.is_file())
+                .filter(|e| extension.map(|x| e.to_string_lossy().ends_with(x)).unwrap_or(true))
+                .map(|e| e.to_string_lossy().to_string())
+                .collect(),
+            Err(_) => vec![]
+        }
+    }
+    else {
+        match glob::glob(location) {
+            Ok(x) => x.filter_map(|e| e.ok())
+                .filter(|e| e.is_file())
+                .map(|e| e.to_string_lossy().to_string())
+                .collect(),
+            Err(e) => {
+                log::error!("Invalid Pattern {} : {:?}", location, e);
+                vec![]
+            }
+        }
+    };
+    result.sort();
+    result
+}
+
+// Manifests and unfinished downloads which are stored next to the data files
+fn is_data_file(path:&str) -> bool {
+    !path.ends_with(".manifest.json") && !path.ends_with(".tmp")
+}
+
+// Expands the local directories and glob patterns of a data list into the files which they contain
+// The files of each location are sorted by path so the order doesn't depend on the file system
+pub fn expand_datasets(datasets:Vec<Dataset>) -> Vec<Dataset> {
+    datasets.into_iter().flat_map(|dataset| {
+        if is_network(&dataset.location) || Path::new(&dataset.location).is_file() {
+            return vec![dataset];
+        }
+        let files:Vec<String> = expand_local_path(&dataset.location, None).into_iter().filter(|e| is_data_file(e)).collect();
+        if files.is_empty() {
+            log::error!("No Files Found {}", dataset.location);
+        }
+        files.into_iter().map(|e| Dataset { location: e, delimiter: dataset.delimiter.clone() }).collect()
+    }).collect()
+}
 
 // Returns the base name of the URL
 pub fn split_path(path:String) -> Option<String> {
@@ -40,119 +133,25 @@ pub fn split_path(path:String) -> Option<String> {
     basename
 }
 
-// Parses the json line and returns a text string
-pub fn create_json_python_text(line:String, tag:&str) -> Option<String> {
-    let v: Value = serde_json::from_str(line.as_str()).unwrap();
+// Parses the json line and returns a text string. Lines which aren't valid json are an error
+pub fn create_json_python_text(line:String, tag:&str) -> Result<Option<String>, serde_json::Error> {
+    let v:Value = serde_json::from_str(&line)?;
     let repo_language = v["meta"]["file_name"].as_str();
     //log::info!("Repo Language {:?}", repo_language);
     if repo_language.is_none() { // meta -- filename not included after filter
-        v[tag].as_str().map(|e| e.to_string())
+        Ok(v[tag].as_str().map(|e| e.to_string()))
     }
     else if repo_language.is_some() && repo_language.unwrap().contains(".py") {
         //log::info!("Found File {:?}", repo_language);
-        v[tag].as_str().map(|e| e.to_string())
+        Ok(v[tag].as_str().map(|e| e.to_string()))
     }
     else {
-        None
+        Ok(None)
     }
 }
 
-// Parses the json line and returns a text string
-pub fn create_json_text(line:String, tag:&str) -> Option<String> {
-    let v: Value = serde_json::from_str(line.as_str()).unwrap();
-    v[tag].as_str().map(|e| e.to_string())
+// Parses the json line and returns a text string. Lines which aren't valid json are an error
+pub fn create_json_text(line:String, tag:&str) -> Result<Option<String>, serde_json::Error> {
+    let v:Value = serde_json::from_str(&line)?;
+    Ok(v[tag].as_str().map(|e| e.to_string()))
 }
-
-// Extract the text from a json file with the string associated with finder
-// This method should be faster than doing a json decode
-pub fn create_text(line:String, finder:&str) -> Option<String>{
-    let search:Vec<char> = finder.chars().collect();
-    let mut index:usize = 0;
-    let mut sp:usize = 0;
-    let mut escape = false;
-    let mut new_text = Vec::<char>::with_capacity(2048);
-    let mut wait = 0;
-    for (i,c) in line.chars().enumerate() {
-        if sp == 0 {
-            if c == search[index] {
-                index += 1;
-                if index == search.len() {
-                    sp = i;
-                }
-            }
-            else {
-                index = 0;
-            }
-        }
-        else {
-            if sp != 0 && c == '"' && !escape {
-                break;
-            }
-            else {
-                if escape && c == 'u' {
-                    wait = 4;
-                }
-                else if wait > 0 {
-                    wait = wait - 1;
-                }
-                if c != '\\' && wait == 0{
-                    new_text.push(c);
-                }
-            }
-        }
-        escape = c == '\\';
-    }
-    //println!("Here {} {}", sp, ep);
-    if new_text.len() > 32 {
-        return Some(new_text.into_iter().collect());
-    }
-    else {
-        return None;
-    }
-
-}
-
-
-pub fn clean_t5(text:String) -> Option<String> {
-    let chars = text.chars();
-    let mut word = String::with_capacity(16);
-    let mut sentence = Vec::<String>::with_capacity(128);
-    let mut result = Vec::<Vec<String>>::with_capacity(32);
-
-    let mut lorem = false;
-
-    for c in chars {
-        if c == '{' {
-            return None;
-        }
-        else if c == ' ' || c == '\n' || c == '\t' || c == '\r' { // whitespace -- create word
-            if word == "lorem" {
-                lorem = true;
-            }
-            if lorem && word == "ipsum" {
-                return None;
-            }
-            if word.len() > 0 && word != "javascript" {
-                    word.push(' ');
-                    sentence.push(word.clone());
-            }
-            word.clear();
-        } 
-        else if c == '.' || c == '!' || c == '?' { // sentence completion
-            word.push(c);
-            word.push(' ');
-            if sentence.len() > 3 {
-                result.push(sentence.clone());
-            }
-            word.clear();
-            sentence.clear();
-        }
-    }
-    if result.len() > 5 {
-        let fin:String = result.into_iter().flatten().collect();
-        Some(fin);   
-    }
-
-    None
-
-}
\ No newline at end of file
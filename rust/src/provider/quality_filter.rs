@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc::{Sender, Receiver}, task::{self, JoinHandle}};
+
+use super::{ProviderChannel, DocumentText};
+
+
+// Heuristic quality filters for pretraining text similar to the C4 and Gopher rules
+// Every rule is optional and only the configured rules are applied. Lines mentioning javascript are removed from
+// the text before the rules which reject a document are checked in order. The first failing rule is counted
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct QualityConfig {
+    #[serde(default)]
+    pub min_words:Option<usize>,
+    #[serde(default)]
+    pub min_sentences:Option<usize>, // Sentences ending with . ! or ?
+    #[serde(default)]
+    pub max_symbol_ratio:Option<f64>, // Hashes and ellipses per word
+    #[serde(default)]
+    pub max_line_repetition:Option<f64>, // Fraction of lines which repeat an earlier line
+    #[serde(default)]
+    pub max_non_ascii:Option<f64>, // Fraction of characters which aren't ascii
+    #[serde(default)]
+    pub bad_words:Vec<String>, // Words or phrases which reject a document
+    #[serde(default)]
+    pub bad_words_file:Option<String>, // File with a bad word or phrase on each line
+    #[serde(default)]
+    pub lorem_ipsum:bool, // Reject placeholder text
+    #[serde(default)]
+    pub javascript:bool // Remove lines which mention javascript
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum QualityRule {
+    Empty,
+    MinWords,
+    MinSentences,
+    SymbolRatio,
+    LineRepetition,
+    NonAscii,
+    BadWords,
+    LoremIpsum
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct QualityStats {
+    pub documents:u64,
+    pub rejected:BTreeMap<QualityRule, u64>
+}
+
+impl QualityStats {
+    pub fn get_rejected(&self) -> u64 {
+        self.rejected.values().sum()
+    }
+}
+
+pub struct QualityFilter {
+    config:QualityConfig,
+    words:HashSet<String>,
+    phrases:Vec<String>,
+    pub stats:QualityStats
+}
+
+fn is_sentence_end(text:&str) -> bool {
+    text.ends_with(['.', '!', '?']) || text.ends_with(".\"") || text.ends_with("!\"") || text.ends_with("?\"")
+}
+
+fn normalize_word(word:&str) -> String {
+    word.trim_matches(|c:char| !c.is_alphanumeric()).to_lowercase()
+}
+
+impl QualityFilter {
+    // Returns an error if the bad words file can't be read
+    pub fn new(config:QualityConfig) -> Result<Self, String> {
+        let mut entries = config.bad_words.clone();
+        if let Some(path) = &config.bad_words_file {
+            let text = std::fs::read_to_string(path).map_err(|e| format!("Can't Read Bad Words {} {:?}", path, e))?;
+            entries.extend(text.lines().map(|e| e.to_string()));
+        }
+        let entries:Vec<String> = entries.iter().map(|e| e.trim().to_lowercase()).filter(|e| !e.is_empty()).collect();
+        let (phrases, words):(Vec<String>, Vec<String>) = entries.into_iter().partition(|e| e.contains(char::is_whitespace));
+        Ok(Self { config, words: words.into_iter().collect(), phrases, stats: QualityStats::default() })
+    }
+
+    // Text without the lines which mention javascript
+    fn remove_lines(&self, text:String) -> String {
+        if !self.config.javascript || !text.to_lowercase().contains("javascript") {
+            return text;
+        }
+        text.lines().filter(|e| !e.to_lowercase().contains("javascript")).collect::<Vec<&str>>().join("\n")
+    }
+
+    // First rule which the text fails
+    pub fn check(&self, text:&str) -> Option<QualityRule> {
+        let config = &self.config;
+        let words:Vec<&str> = text.split_whitespace().collect();
+        if words.is_empty() {
+            return Some(QualityRule::Empty);
+        }
+        if config.min_words.map(|e| words.len() < e).unwrap_or(false) {
+            return Some(QualityRule::MinWords);
+        }
+        if let Some(x) = config.min_sentences {
+            let sentences = words.iter().filter(|e| is_sentence_end(e)).count();
+            if sentences < x {
+                return Some(QualityRule::MinSentences);
+            }
+        }
+        if let Some(x) = config.max_symbol_ratio {
+            let symbols = text.matches('#').count() + text.matches("...").count() + text.matches('\u{2026}').count();
+            if symbols as f64 / words.len() as f64 > x {
+                return Some(QualityRule::SymbolRatio);
+            }
+        }
+        if let Some(x) = config.max_line_repetition {
+            let lines:Vec<&str> = text.lines().map(|e| e.trim()).filter(|e| !e.is_empty()).collect();
+            let unique:HashSet<&str> = lines.iter().copied().collect();
+            let repeated = lines.len() - unique.len();
+            if !lines.is_empty() && repeated as f64 / lines.len() as f64 > x {
+                return Some(QualityRule::LineRepetition);
+            }
+        }
+        if let Some(x) = config.max_non_ascii {
+            let (total, ascii) = text.chars().fold((0usize, 0usize), |a, c| (a.0 + 1, a.1 + c.is_ascii() as usize));
+            if (total - ascii) as f64 / total as f64 > x {
+                return Some(QualityRule::NonAscii);
+            }
+        }
+        if !self.words.is_empty() || !self.phrases.is_empty() || config.lorem_ipsum {
+            let lower = text.to_lowercase();
+            if words.iter().any(|e| self.words.contains(&normalize_word(e))) || self.phrases.iter().any(|e| lower.contains(e.as_str())) {
+                return Some(QualityRule::BadWords);
+            }
+            if config.lorem_ipsum && lower.contains("lorem ipsum") {
+                return Some(QualityRule::LoremIpsum);
+            }
+        }
+        None
+    }
+
+    // Cleaned text or None if the document is rejected
+    pub fn filter(&mut self, text:String) -> Option<String> {
+        self.stats.documents += 1;
+        let text = self.remove_lines(text);
+        match self.check(&text) {
+            Some(rule) => {
+                *self.stats.rejected.entry(rule).or_insert(0) += 1;
+                None
+            },
+            None => Some(text)
+        }
+    }
+}
+
+pub async fn filter_data<T:DocumentText>(mut filter:QualityFilter, mut rx:Receiver<ProviderChannel<T>>, tx:Sender<ProviderChannel<T>>) -> QualityStats {
+
+    while let Some(data) = rx.recv().await {
+        match data {
+            ProviderChannel::Data(mut x) => {
+                if let Some(text) = x.document_text() {
+                    match filter.filter(text.to_string()) {
+                        Some(result) => x.set_document_text(result),
+                        None => continue
+                    }
+                }
+                if tx.send(ProviderChannel::Data(x)).await.is_err() {
+                    break;
+                }
+            },
+            ProviderChannel::Info(x) => {
+                let _ = tx.send(ProviderChannel::Info(x)).await;
+            },
+            ProviderChannel::Error(x) => {
+                let _ = tx.send(ProviderChannel::Error(x)).await;
+            },
+            ProviderChannel::Complete => {
+                let _ = tx.send(ProviderChannel::Complete).await;
+                break;
+            },
+            // Positions count the documents before filtering
+            ProviderChannel::Position(_) | ProviderChannel::Checkpoint(_) => {}
+        }
+    }
+    let stats = filter.stats;
+    log::info!("Quality : {} Documents with {} Rejected", stats.documents, stats.get_rejected());
+    for (rule, count) in &stats.rejected {
+        log::info!("Quality : {:?} Rejected {}", rule, count);
+    }
+    stats
+}
+
+// Start the quality filter stage returning the receiver for the filtered data
+pub fn create_quality_filter<T:DocumentText + Send + 'static>(filter:QualityFilter, rx:Receiver<ProviderChannel<T>>) -> (Receiver<ProviderChannel<T>>, JoinHandle<QualityStats>) {
+    let (tx_filter, rx_filter) = tokio::sync::mpsc::channel::<ProviderChannel<T>>(2);
+    let handle = task::spawn(async move {
+        filter_data(filter, rx, tx_filter).await
+    });
+    (rx_filter, handle)
+}
+
+
+#[test]
+fn test_quality_rules() {
+    let config:QualityConfig = serde_yaml::from_str(r#"
+min_words: 10
+min_sentences: 2
+max_symbol_ratio: 0.1
+max_line_repetition: 0.3
+max_non_ascii: 0.2
+bad_words: [badword, bad phrase]
+lorem_ipsum: true
+javascript: true
+"#).unwrap();
+    let mut filter = QualityFilter::new(config.clone()).unwrap();
+    let good = "This is the first sentence of a good document. It has a second sentence as well!";
+    let cases = [
+        ("", Some(QualityRule::Empty)),
+        ("Too short. Really.", Some(QualityRule::MinWords)),
+        ("This document has enough words but it never ends a single sentence", Some(QualityRule::MinSentences)),
+        ("# Heading # with # many # hashes. And a second sentence which is short.", Some(QualityRule::SymbolRatio)),
+        ("Same line here.\nSame line here.\nSame line here.\nAnother line is different.", Some(QualityRule::LineRepetition)),
+        ("Это русский текст. Он не содержит ascii букв, только пробелы.", Some(QualityRule::NonAscii)),
+        ("This document contains a BadWord, which is rejected. Also a second sentence.", Some(QualityRule::BadWords)),
+        ("This document contains a bad phrase which is rejected. Also a second sentence.", Some(QualityRule::BadWords)),
+        ("Lorem ipsum dolor sit amet, consectetur adipiscing elit. Sed do eiusmod tempor.", Some(QualityRule::LoremIpsum)),
+        (good, None)
+    ];
+    for (text, rule) in cases {
+        assert_eq!(filter.check(text), rule, "{}", text);
+    }
+
+    // Lines mentioning javascript are removed before the rules
+    let text = format!("{}\nPlease enable JavaScript to view this page.", good);
+    assert_eq!(filter.filter(text), Some(good.to_string()));
+    assert_eq!(filter.filter("Short.".to_string()), None);
+    assert_eq!(filter.stats.documents, 2);
+    assert_eq!(filter.stats.rejected.get(&QualityRule::MinWords), Some(&1));
+
+    let missing = QualityConfig { bad_words_file: Some("/missing/bad_words.txt".to_string()), ..config };
+    assert!(QualityFilter::new(missing).is_err());
+}
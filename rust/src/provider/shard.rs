@@ -0,0 +1,52 @@
+// Partition of the data between the ranks of a distributed run
+// Each rank sees a disjoint part of the data. Arrow rows are split evenly so the epochs of the ranks stay aligned.
+// File lists are split by file count and the remaining files (less than the number of ranks) by document, so the
+// ranks only read the same number of documents per epoch when the files are of equal size. Runs with files of
+// unequal size should set the length in iterations so every rank stops after the same number of documents
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shard {
+    pub rank:usize,
+    pub world_size:usize
+}
+
+impl Shard {
+    pub fn new(rank:usize, world_size:usize) -> Self {
+        Self { rank, world_size }
+    }
+
+    pub fn contains(&self, index:usize) -> bool {
+        index % self.world_size == self.rank
+    }
+
+    // Datasets of the rank with a flag set for the datasets where the documents are split between the ranks
+    pub fn split_datasets<T:Clone>(&self, datasets:&[T]) -> Vec<(T, bool)> {
+        let whole = datasets.len() - datasets.len() % self.world_size;
+        let files = datasets[..whole].iter().enumerate().filter(|(index, _)| self.contains(*index)).map(|(_, e)| (e.clone(), false));
+        let shared = datasets[whole..].iter().map(|e| (e.clone(), true));
+        files.chain(shared).collect()
+    }
+
+    // Rows used by each rank. The remainder is dropped so every rank has the same epoch length
+    pub fn get_rows(&self, rows:usize) -> usize {
+        rows / self.world_size
+    }
+
+    // Check if the row at the index of the epoch order belongs to the rank
+    pub fn contains_row(&self, index:usize, rows:usize) -> bool {
+        self.contains(index) && index < self.get_rows(rows) * self.world_size
+    }
+}
+
+
+#[test]
+pub fn test_split_datasets() {
+    let datasets:Vec<usize> = (0..5).collect();
+    let first = Shard::new(0, 2).split_datasets(&datasets);
+    let second = Shard::new(1, 2).split_datasets(&datasets);
+    assert_eq!(first, vec![(0, false), (2, false), (4, true)]);
+    assert_eq!(second, vec![(1, false), (3, false), (4, true)]);
+    // Rows past the last full set of ranks are dropped
+    let shard = Shard::new(1, 2);
+    assert_eq!((0..5).filter(|e| shard.contains_row(*e, 5)).collect::<Vec<usize>>(), vec![1, 3]);
+}
@@ -0,0 +1,114 @@
+use rand::{Rng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc::{Sender, Receiver}, task::{self, JoinHandle}};
+
+use crate::random::{self, RandomStream};
+
+use super::ProviderChannel;
+
+
+// Approximate shuffling for streamed sources which can't be shuffled in memory
+// Items fill a bounded buffer and once it is full every new item replaces a random item which is sent on
+// The remaining items are sent in random order when the provider completes
+
+pub const DEFAULT_BUFFER_SIZE:usize = 16384;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ShuffleBufferConfig {
+    pub size:usize,
+    pub seed:Option<u64>
+}
+
+impl ShuffleBufferConfig {
+    pub fn new(size:usize, seed:Option<u64>) -> Self {
+        Self { size, seed }
+    }
+}
+
+pub async fn shuffle_data<T>(config:ShuffleBufferConfig, mut rx:Receiver<ProviderChannel<T>>, tx:Sender<ProviderChannel<T>>) {
+    // A seed of 0 is used without a seed so a resume skips the documents of the same order
+    let mut rng = random::create_rng(Some(config.seed.unwrap_or(0)), RandomStream::Buffer, 0);
+    let size = std::cmp::max(config.size, 1);
+    let mut buffer = Vec::<T>::with_capacity(size);
+
+    while let Some(data) = rx.recv().await {
+        match data {
+            ProviderChannel::Data(x) => {
+                if buffer.len() < size {
+                    buffer.push(x);
+                    continue;
+                }
+                let index = rng.gen_range(0..size);
+                let result = std::mem::replace(&mut buffer[index], x);
+                if tx.send(ProviderChannel::Data(result)).await.is_err() {
+                    return;
+                }
+            },
+            ProviderChannel::Info(x) => {
+                let _ = tx.send(ProviderChannel::Info(x)).await;
+            },
+            ProviderChannel::Error(x) => {
+                let _ = tx.send(ProviderChannel::Error(x)).await;
+            },
+            ProviderChannel::Complete => break,
+            // Positions are invalid once the data is shuffled
+            ProviderChannel::Position(_) | ProviderChannel::Checkpoint(_) => {}
+        }
+    }
+
+    buffer.shuffle(&mut rng);
+    for x in buffer {
+        if tx.send(ProviderChannel::Data(x)).await.is_err() {
+            return;
+        }
+    }
+    let _ = tx.send(ProviderChannel::Complete).await;
+}
+
+// Start the shuffle stage returning the receiver for the shuffled data
+pub fn create_shuffle_buffer<T:Send + 'static>(config:ShuffleBufferConfig, rx:Receiver<ProviderChannel<T>>) -> (Receiver<ProviderChannel<T>>, JoinHandle<()>) {
+    let (tx_shuffle, rx_shuffle) = tokio::sync::mpsc::channel::<ProviderChannel<T>>(2);
+    let handle = task::spawn(async move {
+        shuffle_data(config, rx, tx_shuffle).await;
+    });
+    (rx_shuffle, handle)
+}
+
+
+#[tokio::test]
+async fn test_shuffle_buffer() {
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<u32>>(2);
+    tokio::spawn(async move {
+        for x in 0..100 {
+            let _ = tx.send(ProviderChannel::Data(x)).await;
+        }
+        let _ = tx.send(ProviderChannel::Complete).await;
+    });
+    let (rx_shuffle, _) = create_shuffle_buffer(ShuffleBufferConfig::new(10, Some(1)), rx);
+
+    let mut values = super::test_util::collect_channel(rx_shuffle).await.data;
+    assert_ne!(values, (0..100).collect::<Vec<u32>>());
+    // Items are delayed by at most the size of the buffer
+    assert!(values.iter().take(10).all(|e| *e < 20));
+    values.sort();
+    assert_eq!(values, (0..100).collect::<Vec<u32>>());
+}
+
+#[tokio::test]
+async fn test_unseeded_order() {
+    // The resume drops the documents which were consumed so the order without a seed has to be repeatable
+    let mut runs = Vec::<Vec<u32>>::new();
+    for _ in 0..2 {
+        let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<u32>>(2);
+        tokio::spawn(async move {
+            for x in 0..100 {
+                let _ = tx.send(ProviderChannel::Data(x)).await;
+            }
+            let _ = tx.send(ProviderChannel::Complete).await;
+        });
+        let (rx_shuffle, _) = create_shuffle_buffer(ShuffleBufferConfig::new(10, None), rx);
+        runs.push(super::test_util::collect_channel(rx_shuffle).await.data);
+    }
+    assert_ne!(runs[0], (0..100).collect::<Vec<u32>>());
+    assert_eq!(runs[0], runs[1]);
+}
@@ -1,4 +1,6 @@
-use serde::{Deserialize, Serialize};
+use regex::Regex;
+use serde::{Deserialize, Serialize, Deserializer, Serializer};
+use serde_json::Value;
 
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -8,16 +10,145 @@ pub enum SourceFilter {
     #[serde(rename = "fast_text")]
     FastText,
     #[serde(rename = "python_text")]
-    PythonText 
+    PythonText,
+    #[serde(rename = "json")]
+    Json(JsonFilter),
+    #[serde(rename = "line")]
+    Line // Each non empty line is a plain text document
 }
 
 impl SourceFilter {
-    pub fn get_text(&self, line:String) -> Option<String> {
+    // Text of the line or None if the line is filtered. Lines which aren't valid json are an error so the
+    // provider can count them
+    pub fn parse_text(&self, line:String) -> Result<Option<String>, serde_json::Error> {
         match self {
             SourceFilter::JsonText => super::provider_util::create_json_text(line, "text"),
             SourceFilter::PythonText => super::provider_util::create_json_python_text(line, "text"),
-            SourceFilter::FastText => None,
+            SourceFilter::FastText => super::fast_text::get_text(line, "text"),
+            SourceFilter::Json(x) => x.get_text(&line),
+            SourceFilter::Line => Ok(if line.trim().is_empty() {None} else {Some(line)}),
+        }
+    }
+
+    // Text of the line where invalid lines are filtered
+    pub fn get_text(&self, line:String) -> Option<String> {
+        self.parse_text(line).ok().flatten()
+    }
+}
+
+// Regex which is compiled when the configuration is loaded
+#[derive(Debug, Clone)]
+pub struct Pattern(pub Regex);
+
+impl Serialize for Pattern {
+    fn serialize<S:Serializer>(&self, serializer:S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl <'de>Deserialize<'de> for Pattern {
+    fn deserialize<D:Deserializer<'de>>(deserializer:D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern).map(Pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub enum Condition {
+    #[serde(rename = "equals")]
+    Equals(String),
+    #[serde(rename = "contains")]
+    Contains(String),
+    #[serde(rename = "regex")]
+    Regex(Pattern),
+    #[serde(rename = "suffix")]
+    Suffix(String)
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FieldPredicate {
+    pub field:String,
+    pub condition:Condition
+}
+
+// Generic filter for json lines
+// Fields are json pointers (/meta/title) or dotted paths (meta.title). The text is the concatenation of the
+// string fields which exist and the document is only used if every predicate matches
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct JsonFilter {
+    pub fields:Vec<String>,
+    #[serde(default)]
+    pub separator:Option<String>, // Separator between the fields. Defaults to a blank line
+    #[serde(default)]
+    pub predicates:Vec<FieldPredicate>
+}
+
+fn get_field<'a>(value:&'a Value, field:&str) -> Option<&'a Value> {
+    if field.starts_with('/') {
+        value.pointer(field)
+    }
+    else {
+        value.pointer(&format!("/{}", field.replace('.', "/")))
+    }
+}
+
+impl FieldPredicate {
+    fn matches(&self, value:&Value) -> bool {
+        let field = match get_field(value, &self.field) {
+            Some(Value::String(x)) => x.to_owned(),
+            Some(Value::Null) | None => return false,
+            Some(x) => x.to_string()
+        };
+        match &self.condition {
+            Condition::Equals(x) => &field == x,
+            Condition::Contains(x) => field.contains(x.as_str()),
+            Condition::Regex(x) => x.0.is_match(&field),
+            Condition::Suffix(x) => field.ends_with(x.as_str())
+        }
+    }
+}
+
+impl JsonFilter {
+    pub fn new(fields:Vec<&str>, predicates:Vec<FieldPredicate>) -> Self {
+        Self { fields: fields.into_iter().map(|e| e.to_string()).collect(), separator: None, predicates }
+    }
+
+    pub fn get_text(&self, line:&str) -> Result<Option<String>, serde_json::Error> {
+        let value:Value = serde_json::from_str(line)?;
+        if !self.predicates.iter().all(|e| e.matches(&value)) {
+            return Ok(None);
+        }
+        let texts:Vec<&str> = self.fields.iter()
+            .filter_map(|e| get_field(&value, e).and_then(|v| v.as_str()))
+            .filter(|e| !e.is_empty())
+            .collect();
+        if texts.is_empty() {
+            Ok(None)
+        }
+        else {
+            Ok(Some(texts.join(self.separator.as_deref().unwrap_or("\n\n"))))
         }
-        
     }
-}
\ No newline at end of file
+}
+
+
+#[test]
+pub fn test_json_filter() {
+    let config = r#"
+!json
+fields: [title, /body/text]
+predicates:
+  - field: meta.file_name
+    condition: !suffix .py
+  - field: /meta/stars
+    condition: !regex ^[1-9][0-9]+$
+"#;
+    let filter:SourceFilter = serde_yaml::from_str(config).unwrap();
+    let line = r#"{"title":"Title","body":{"text":"Body"},"meta":{"file_name":"a.py","stars":12}}"#;
+    assert_eq!(filter.get_text(line.to_string()), Some("Title\n\nBody".to_string()));
+    // Predicates which don't match and missing fields remove the document
+    assert_eq!(filter.get_text(line.replace("a.py", "a.rs")), None);
+    assert_eq!(filter.get_text(line.replace("12", "3")), None);
+    assert_eq!(filter.get_text(line.replace("\"title\":\"Title\",", "")), Some("Body".to_string()));
+    assert!(serde_yaml::from_str::<SourceFilter>("!json\nfields: [text]\npredicates: [{field: a, condition: !regex '('}]").is_err());
+}
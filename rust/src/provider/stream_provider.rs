@@ -0,0 +1,84 @@
+use std::pin::Pin;
+
+use tokio::{io::{AsyncBufReadExt, AsyncRead, BufReader}, sync::mpsc::Sender};
+
+use crate::checkpoint::{DataCheckpoint, DataPosition};
+
+use super::{ProviderChannel, general_file_provider::Counter, text_file_provider, source_filter::SourceFilter, provider_config::ProviderLength};
+
+
+// Documents streamed from standard input or a named pipe with one document on each line
+// The lines go through the SourceFilter so json from other tools can be piped directly into training
+// A stream can only be read once so the provider completes at the end of the input for any number of epochs
+
+async fn create_reader(path:Option<&str>) -> Option<Pin<Box<dyn AsyncRead + Send>>> {
+    match path {
+        Some(x) => match tokio::fs::File::open(x).await {
+            Ok(file) => Some(Box::pin(file)),
+            Err(e) => {
+                log::error!("Can't Open Pipe {} {:?}", x, e);
+                None
+            }
+        },
+        None => Some(Box::pin(tokio::io::stdin()))
+    }
+}
+
+pub async fn load_stream(path:Option<String>, length:ProviderLength, tx:Sender<ProviderChannel<String>>, filter:&SourceFilter, resume:Option<DataCheckpoint>) {
+    if let ProviderLength::Epochs { epochs } = length {
+        if epochs > 1 {
+            log::info!("Stream Input is only Read for a Single Epoch");
+        }
+    }
+    let mut counter = Counter::new(length);
+
+    // A checkpoint skips the documents already used when the same input is sent again
+    let offset = match resume.as_ref().and_then(|e| e.position.clone()) {
+        Some(x) => {
+            log::info!("Resuming Stream at {:?}", x);
+            counter.count = resume.as_ref().map(|e| e.documents).unwrap_or(0);
+            x.offset
+        },
+        None => 0
+    };
+    counter.start_dataset(offset, None);
+
+    let _ = tx.send(ProviderChannel::Info(crate::tasks::DatasetInfo { name: "stream".to_string(), length: 1000000000 })).await;
+    let _ = tx.send(ProviderChannel::Position(DataPosition { epoch: 0, dataset: 0, offset })).await;
+    if let Some(reader) = create_reader(path.as_deref()).await {
+        let lines = BufReader::with_capacity(100000, reader).lines();
+        text_file_provider::send_lines(lines, &mut counter, &tx, filter).await;
+    }
+    log::info!("Finished Stream Provider with {} Invalid Json Lines", counter.invalid);
+    let _ = tx.send(ProviderChannel::Complete).await;
+}
+
+
+#[tokio::test]
+async fn test_stream_pipe() {
+    use std::io::Write;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("input.pipe");
+    let status = std::process::Command::new("mkfifo").arg(&path).status().unwrap();
+    assert!(status.success());
+
+    // The writer blocks until the provider opens the pipe
+    let writer_path = path.clone();
+    let writer = std::thread::spawn(move || {
+        let mut file = std::fs::OpenOptions::new().write(true).open(writer_path).unwrap();
+        for index in 0..5 {
+            writeln!(file, "{{\"text\": \"document {}\"}}", index).unwrap();
+            // Blank and broken lines are skipped
+            writeln!(file, "{}", if index % 2 == 0 {""} else {"{\"text\": broken"}).unwrap();
+        }
+    });
+
+    let location = path.to_string_lossy().to_string();
+    let resume = DataCheckpoint { position: Some(DataPosition { epoch: 0, dataset: 0, offset: 1 }), documents: 1, batches: 0, carry: vec![] };
+    let values = super::test_util::run_provider(|tx| async move {
+        load_stream(Some(location), ProviderLength::Epochs { epochs: 2 }, tx, &SourceFilter::JsonText, Some(resume)).await;
+    }).await.data;
+    writer.join().unwrap();
+    assert_eq!(values, (1..5).map(|e| format!("document {}", e)).collect::<Vec<String>>());
+}
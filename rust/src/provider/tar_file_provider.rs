@@ -0,0 +1,252 @@
+use std::{path::PathBuf, io::{Read, BufRead}};
+
+use super::{ProviderChannel, general_file_provider::Counter, url_reader::{self, DownloadConfig}, cache_writer::CacheWriter, source_filter::SourceFilter, provider_config::Dataset, provider_util::DownloadType, compressed_file_provider::{Decoder, create_decoder, get_magic_type}};
+use tokio::{io::{AsyncBufRead, AsyncBufReadExt, BufReader}, fs::File};
+
+use tokio::sync::mpsc::{Sender, Receiver};
+use tokio_util::io::SyncIoBridge;
+
+
+// Streams through the members of a tar archive which is optionally compressed (.tar, .tar.gz, .tar.zst, ...)
+// Each member is converted into documents based on the extension :
+//   .jsonl : Every line is passed through the SourceFilter
+//   .json  : Either an array of strings or objects (passed through the SourceFilter) or a single object
+//   other  : The full member is a single plain text document
+
+pub fn is_tar(path:&str) -> bool {
+    path.ends_with(".tar") || path.contains(".tar.") || path.ends_with(".tgz")
+}
+
+// Text of the data which is only copied if it isn't valid utf8
+fn to_text(data:Vec<u8>) -> String {
+    String::from_utf8(data).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+}
+
+// Messages from the blocking thread which reads the archive
+enum ArchiveMessage {
+    Document(String),
+    Invalid(serde_json::Error), // Line of a jsonl member which isn't valid json
+    Complete // Sent after the last member once the archive was read without errors
+}
+
+// Document of a json array element. Strings are the text of the document while objects use the filter
+fn get_element(element:serde_json::Value, filter:&SourceFilter) -> Option<String> {
+    match element {
+        serde_json::Value::String(x) if x.trim().is_empty() => None,
+        serde_json::Value::String(x) => Some(x),
+        x => filter.get_text(x.to_string())
+    }
+}
+
+// Documents contained in a single json or text member of the archive
+fn get_documents(name:&str, contents:String, filter:&SourceFilter) -> Vec<String> {
+    if name.ends_with(".json") {
+        match serde_json::from_str::<serde_json::Value>(&contents) {
+            Ok(serde_json::Value::Array(x)) => x.into_iter().filter_map(|e| get_element(e, filter)).collect(),
+            Ok(_) => filter.get_text(contents).into_iter().collect(),
+            Err(e) => {
+                log::error!("Can't Parse Member {} {:?}", name, e);
+                vec![]
+            }
+        }
+    }
+    else if contents.trim().is_empty() {
+        vec![]
+    }
+    else {
+        vec![contents]
+    }
+}
+
+// Sends the documents of a jsonl member as the lines are read so the member isn't held in memory
+// Returns false if the member can't be read or the receiver is closed
+fn send_lines<R:Read>(entry:R, name:&str, filter:&SourceFilter, tx:&Sender<ArchiveMessage>) -> bool {
+    let mut reader = std::io::BufReader::new(entry);
+    loop {
+        let mut line = Vec::<u8>::new();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => return true,
+            Ok(_) => {},
+            Err(e) => {
+                log::error!("Error in Member Read {} {:?}", name, e);
+                return false;
+            }
+        }
+        while line.last().map(|e| *e == b'\n' || *e == b'\r').unwrap_or(false) {
+            line.pop();
+        }
+        let message = match filter.parse_text(to_text(line)) {
+            Ok(Some(x)) => ArchiveMessage::Document(x),
+            Ok(None) => continue,
+            Err(e) => ArchiveMessage::Invalid(e)
+        };
+        if tx.blocking_send(message).is_err() {
+            return false;
+        }
+    }
+}
+
+// The tar crate is synchronous so the archive is read on a blocking thread which sends the documents back
+fn read_archive(decoder:Decoder, filter:&SourceFilter) -> Receiver<ArchiveMessage> {
+    let (tx, rx) = tokio::sync::mpsc::channel::<ArchiveMessage>(64);
+    let bridge = SyncIoBridge::new(decoder);
+    let filter = filter.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut archive = tar::Archive::new(bridge);
+        let entries = match archive.entries() {
+            Ok(x) => x,
+            Err(e) => {
+                log::error!("Can't Read Archive {:?}", e);
+                return;
+            }
+        };
+        for entry in entries {
+            let mut entry = match entry {
+                Ok(x) => x,
+                Err(e) => {
+                    log::error!("Error in Archive Read {:?}", e);
+                    return;
+                }
+            };
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let name = entry.path().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+            if name.ends_with(".jsonl") {
+                if !send_lines(&mut entry, &name, &filter, &tx) {
+                    return;
+                }
+                continue;
+            }
+            let mut data = Vec::<u8>::new();
+            if let Err(e) = entry.read_to_end(&mut data) {
+                log::error!("Error in Member Read {} {:?}", name, e);
+                return;
+            }
+            for document in get_documents(&name, to_text(data), &filter) {
+                if tx.blocking_send(ArchiveMessage::Document(document)).is_err() {
+                    return;
+                }
+            }
+        }
+        let _ = tx.blocking_send(ArchiveMessage::Complete);
+    });
+    rx
+}
+
+// Archives without a compression extension or magic bytes are read directly
+async fn create_archive_decoder<R:AsyncBufRead + Send + Unpin + 'static>(mut reader:R, typ:DownloadType) -> Option<Decoder> {
+    let typ = match typ {
+        DownloadType::Error => get_magic_type(reader.fill_buf().await.ok()?),
+        x => x
+    };
+    match typ {
+        DownloadType::Error => Some(Box::pin(reader)),
+        x => create_decoder(reader, x).await
+    }
+}
+
+async fn send_documents(decoder:Decoder,
+    counter:&mut Counter,
+    tx:&Sender<ProviderChannel<String>>,
+    mut cache_writer:Option<CacheWriter>,
+    filter:&SourceFilter) {
+
+    let mut documents = read_archive(decoder, filter);
+    while let Some(message) = documents.recv().await {
+        let x = match message {
+            ArchiveMessage::Document(x) => x,
+            ArchiveMessage::Invalid(e) => {
+                counter.inc_invalid(&e);
+                continue;
+            },
+            ArchiveMessage::Complete => {
+                if let Some(writer) = cache_writer.as_mut() {
+                    writer.finish();
+                }
+                return;
+            }
+        };
+        if let Some(writer) = cache_writer.as_mut() {
+            writer.write_line(x.to_owned());
+        }
+        if counter.skip_data() {
+            continue;
+        }
+        let _res = tx.send(ProviderChannel::Data(x)).await;
+        if counter.inc_data() {
+            return;
+        }
+    }
+    // The archive thread stopped on an error before the end of the archive
+    counter.errors += 1;
+}
+
+pub async fn load_dataset(path:&PathBuf, typ:DownloadType, counter:&mut Counter, tx:&Sender<ProviderChannel<String>>, filter:&SourceFilter) {
+    let file = match File::open(path).await {
+        Ok(x) => x,
+        Err(_) => {
+            log::error!("File Not Found {:?}", path);
+            counter.errors += 1;
+            return;
+        }
+    };
+    match create_archive_decoder(BufReader::new(file), typ).await {
+        Some(decoder) => send_documents(decoder, counter, tx, None, filter).await,
+        None => counter.errors += 1
+    }
+}
+
+pub async fn load_url(dataset:&Dataset,
+    typ:DownloadType,
+    counter:&mut Counter,
+    tx:&Sender<ProviderChannel<String>>,
+    cache_writer:Option<CacheWriter>,
+    filter:&SourceFilter) {
+
+    let stream = url_reader::create_url_reader(&dataset.location, DownloadConfig::default());
+    match create_archive_decoder(BufReader::new(stream), typ).await {
+        Some(decoder) => send_documents(decoder, counter, tx, cache_writer, filter).await,
+        None => counter.errors += 1
+    }
+}
+
+
+#[tokio::test]
+async fn test_tar_members() {
+    use super::provider_config::ProviderLength;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut builder = tar::Builder::new(Vec::<u8>::new());
+    let members:[(&str, &[u8]);5] = [
+        ("books/first.txt", b"plain text book"),
+        ("wiki/part.json", br#"[{"text":"article one"},{"text":"article two"}]"#),
+        // Wikipedia in the Pile is an array of the article strings
+        ("wikipedia-en/wiki.json", br#"["first article", "", "second article"]"#),
+        ("data/lines.jsonl", b"{\"text\":\"line one\"}\n{\"text\": broken\n{\"text\":\"line two\"}\n"),
+        // Windows line endings and bytes which aren't utf8
+        ("data/latin.jsonl", b"{\"text\":\"caf\xe9\"}\r\n{\"text\":\"last\"}")
+    ];
+    for (name, contents) in members {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_cksum();
+        builder.append_data(&mut header, name, contents).unwrap();
+    }
+    let archive = builder.into_inner().unwrap();
+
+    let path = dir.path().join("data.tar.gz");
+    let mut compressed = Vec::<u8>::new();
+    tokio::io::AsyncReadExt::read_to_end(&mut async_compression::tokio::bufread::GzipEncoder::new(&archive[..]), &mut compressed).await.unwrap();
+    std::fs::write(&path, compressed).unwrap();
+    assert!(is_tar(&path.to_string_lossy()));
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<String>>(16);
+    let mut counter = Counter::new(ProviderLength::Epochs { epochs: 1 });
+    load_dataset(&path, DownloadType::Gzip, &mut counter, &tx, &SourceFilter::JsonText).await;
+    drop(tx);
+
+    let values = super::test_util::collect_channel(rx).await.data;
+    assert_eq!(values, vec!["plain text book", "article one", "article two", "first article", "second article", "line one", "line two", "caf\u{FFFD}", "last"]);
+    assert_eq!(counter.invalid, 1);
+}
@@ -0,0 +1,74 @@
+use std::{fs::File, future::Future, path::Path, sync::Arc};
+
+use arrow::{array::Int64Array, datatypes::{DataType, Field, Schema}, ipc::writer::{FileWriter, StreamWriter}, record_batch::RecordBatch};
+use tokio::sync::mpsc::{Receiver, Sender};
+
+use crate::{checkpoint::DataPosition, tasks::DatasetInfo};
+
+use super::ProviderChannel;
+
+
+// Fixtures and channel helpers shared by the provider tests
+
+// Messages of a provider run split by type
+pub struct Collected<T> {
+    pub data:Vec<T>,
+    pub positions:Vec<DataPosition>,
+    pub info:Vec<DatasetInfo>,
+    pub errors:Vec<String>
+}
+
+// Reads the channel until the provider completes or the channel is closed
+pub async fn collect_channel<T>(mut rx:Receiver<ProviderChannel<T>>) -> Collected<T> {
+    let mut collected = Collected { data: vec![], positions: vec![], info: vec![], errors: vec![] };
+    while let Some(data) = rx.recv().await {
+        match data {
+            ProviderChannel::Data(x) => collected.data.push(x),
+            ProviderChannel::Position(x) => collected.positions.push(x),
+            ProviderChannel::Info(x) => collected.info.push(x),
+            ProviderChannel::Error(x) => collected.errors.push(x),
+            ProviderChannel::Checkpoint(_) => {},
+            ProviderChannel::Complete => break
+        }
+    }
+    collected
+}
+
+// Spawns the provider with a new channel and collects its output
+pub async fn run_provider<T, F, R>(create:F) -> Collected<T>
+where T:Send + 'static, F:FnOnce(Sender<ProviderChannel<T>>) -> R, R:Future<Output=()> + Send + 'static {
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<T>>(64);
+    tokio::spawn(create(tx));
+    collect_channel(rx).await
+}
+
+// Plain text file with the documents separated by a blank line
+pub fn write_documents(path:&Path, documents:&[&str]) -> String {
+    std::fs::write(path, documents.join("\n\n")).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+// Json lines file with a {"text": ...} object for each document
+pub fn write_json_lines<S:AsRef<str>>(path:&Path, documents:&[S]) -> String {
+    let lines:Vec<String> = documents.iter().map(|e| serde_json::json!({"text": e.as_ref()}).to_string()).collect();
+    std::fs::write(path, lines.join("\n")).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+// Arrow file with a record batch of the "value" column for each list of values in the stream or file format
+pub fn write_arrow(path:&Path, batches:&[Vec<i64>], stream:bool) -> String {
+    let schema = Arc::new(Schema::new(vec![Field::new("value", DataType::Int64, false)]));
+    let batches:Vec<RecordBatch> = batches.iter().map(|e| RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(e.clone()))]).unwrap()).collect();
+    let file = File::create(path).unwrap();
+    if stream {
+        let mut writer = StreamWriter::try_new(file, &schema).unwrap();
+        batches.iter().for_each(|e| writer.write(e).unwrap());
+        writer.finish().unwrap();
+    }
+    else {
+        let mut writer = FileWriter::try_new(file, &schema).unwrap();
+        batches.iter().for_each(|e| writer.write(e).unwrap());
+        writer.finish().unwrap();
+    }
+    path.to_string_lossy().to_string()
+}
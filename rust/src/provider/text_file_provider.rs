@@ -0,0 +1,140 @@
+use std::path::PathBuf;
+
+use super::{ProviderChannel, general_file_provider::Counter, source_filter::SourceFilter};
+use tokio::{io::{AsyncBufRead, AsyncBufReadExt, BufReader, Lines}, fs::File};
+
+use tokio::sync::mpsc::Sender;
+
+
+// Uncompressed local files which allows working with small corpora without compressing them first
+// JSON lines files use the SourceFilter while plain text files are split into documents by a delimiter line
+
+pub async fn create_lines(file_path:&PathBuf) -> Option<Lines<BufReader<File>>> {
+    let file_opt = File::open(file_path).await;
+    match file_opt {
+        Ok(file) => {
+            let buf_reader = BufReader::with_capacity(100000, file);
+            Some(buf_reader.lines())
+        },
+        Err(_) => {
+            log::error!("File Not Found {:?}", file_path);
+            None
+        }
+    }
+}
+
+pub async fn load_dataset(path:&PathBuf, counter:&mut Counter, tx:&Sender<ProviderChannel<String>>, filter:&SourceFilter) {
+    let lines_opt = create_lines(path).await;
+    if lines_opt.is_none() {
+        counter.errors += 1;
+        return;
+    }
+    send_lines(lines_opt.unwrap(), counter, tx, filter).await;
+}
+
+// Send the filtered lines of the reader as documents
+pub async fn send_lines<R:AsyncBufRead + Unpin>(mut lines:Lines<R>, counter:&mut Counter, tx:&Sender<ProviderChannel<String>>, filter:&SourceFilter) {
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(x)) => x,
+            Ok(None) => return,
+            Err(e) => {
+                log::error!("Error in File Read {:?}", e);
+                counter.errors += 1;
+                return;
+            }
+        };
+        let text = filter.parse_text(line);
+        match text {
+            Ok(Some(_)) if counter.skip_data() => continue,
+            Ok(Some(x)) => {
+                let _res_ = tx.send(ProviderChannel::Data(x)).await;
+                if counter.inc_data() {
+                    return;
+                }
+            },
+            Ok(None) => {
+                continue
+            },
+            Err(e) => counter.inc_invalid(&e)
+        }
+    }
+}
+
+// Plain text where each document ends at the delimiter line (blank line by default)
+pub async fn load_text_dataset(path:&PathBuf, delimiter:&Option<String>, counter:&mut Counter, tx:&Sender<ProviderChannel<String>>) {
+    let lines_opt = create_lines(path).await;
+    if lines_opt.is_none() {
+        counter.errors += 1;
+        return;
+    }
+    let mut lines = lines_opt.unwrap();
+    let delimiter = delimiter.as_deref().unwrap_or("").trim();
+
+    let mut document = Vec::<String>::new();
+    loop {
+        let line = match lines.next_line().await {
+            Ok(x) => x,
+            Err(e) => {
+                log::error!("Error in File Read {:?}", e);
+                counter.errors += 1;
+                None
+            }
+        };
+        let end = match &line {
+            Some(x) => x.trim() == delimiter,
+            None => true
+        };
+        if end {
+            if !document.is_empty() && !counter.skip_data() {
+                let _res_ = tx.send(ProviderChannel::Data(document.join("\n"))).await;
+                if counter.inc_data() {
+                    return;
+                }
+            }
+            document.clear();
+            if line.is_none() {
+                return;
+            }
+        }
+        else {
+            document.push(line.unwrap());
+        }
+    }
+}
+
+
+#[tokio::test]
+async fn test_text_delimiter() {
+    use super::provider_config::ProviderLength;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.txt");
+    std::fs::write(&path, "first line\nsecond line\n\n\nnext document\n===\nlast").unwrap();
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<String>>(16);
+    let mut counter = Counter::new(ProviderLength::Epochs { epochs: 1 });
+    load_text_dataset(&path, &None, &mut counter, &tx).await;
+    load_text_dataset(&path, &Some("===".to_string()), &mut counter, &tx).await;
+    drop(tx);
+
+    let values = super::test_util::collect_channel(rx).await.data;
+    assert_eq!(values, vec!["first line\nsecond line", "next document\n===\nlast", "first line\nsecond line\n\n\nnext document", "last"]);
+}
+
+#[tokio::test]
+async fn test_invalid_lines() {
+    use tokio::io::AsyncBufReadExt;
+    use super::provider_config::ProviderLength;
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<ProviderChannel<String>>(16);
+    let mut counter = Counter::new(ProviderLength::Epochs { epochs: 1 });
+    let input = "{\"text\": \"first\"}\n\n{\"text\": broken\n{\"title\": \"none\"}\n{\"text\": \"second\"}\n";
+    send_lines(input.as_bytes().lines(), &mut counter, &tx, &SourceFilter::JsonText).await;
+    drop(tx);
+
+    let values = super::test_util::collect_channel(rx).await.data;
+    // The blank and broken lines are counted by the provider while the line without text is only filtered
+    assert_eq!(values, vec!["first", "second"]);
+    assert_eq!(counter.invalid, 2);
+}
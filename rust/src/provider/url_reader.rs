@@ -0,0 +1,163 @@
+use std::{pin::Pin, time::Duration};
+
+use bytes::Bytes;
+use reqwest::{Client, Response, StatusCode, header::RANGE};
+use tokio::io::AsyncRead;
+use tokio_util::io::StreamReader;
+
+
+// Reader for a url which retries failed requests and continues from the last byte received with a range request
+// A stalled connection is detected with the read timeout. The delay between attempts doubles up to the maximum
+// and is reset once data is received so a long download can survive any number of separate network errors
+
+#[derive(Debug, Clone)]
+pub struct DownloadConfig {
+    pub connect_timeout:Duration,
+    pub read_timeout:Duration, // Maximum time without data
+    pub retries:usize, // Attempts without data before the download fails
+    pub backoff:Duration, // Delay before the first retry
+    pub max_backoff:Duration
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(30),
+            read_timeout: Duration::from_secs(120),
+            retries: 8,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(120)
+        }
+    }
+}
+
+struct Download {
+    client:Client,
+    url:String,
+    config:DownloadConfig,
+    response:Option<Response>,
+    offset:u64, // Bytes received
+    discard:u64, // Bytes to drop when the server ignores the range and sends the data from the start
+    attempts:usize
+}
+
+impl Download {
+    // Request the data from the offset. Returns None if there is no data remaining
+    async fn connect(&mut self) -> Result<Option<Response>, String> {
+        let mut request = self.client.get(&self.url);
+        if self.offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", self.offset));
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        match response.status() {
+            StatusCode::PARTIAL_CONTENT => Ok(Some(response)),
+            StatusCode::RANGE_NOT_SATISFIABLE if self.offset > 0 => Ok(None),
+            x if x.is_success() => {
+                self.discard = self.offset;
+                Ok(Some(response))
+            },
+            x => {
+                // Client errors other than timeouts and rate limits won't succeed on a retry
+                if x.is_client_error() && x != StatusCode::REQUEST_TIMEOUT && x != StatusCode::TOO_MANY_REQUESTS {
+                    self.attempts = self.config.retries;
+                }
+                Err(format!("Status {}", x))
+            }
+        }
+    }
+
+    fn get_backoff(&self) -> Duration {
+        let scale = 1u32.checked_shl(self.attempts as u32 - 1).unwrap_or(u32::MAX);
+        std::cmp::min(self.config.backoff.saturating_mul(scale), self.config.max_backoff)
+    }
+
+    // Next block of data which is None at the end of the data
+    async fn next_chunk(&mut self) -> Option<std::io::Result<Bytes>> {
+        loop {
+            let error = match self.response.as_mut() {
+                Some(response) => match tokio::time::timeout(self.config.read_timeout, response.chunk()).await {
+                    Ok(Ok(Some(chunk))) => {
+                        self.attempts = 0;
+                        let skip = std::cmp::min(self.discard, chunk.len() as u64);
+                        self.discard -= skip;
+                        let chunk = chunk.slice(skip as usize..);
+                        self.offset += chunk.len() as u64;
+                        if chunk.is_empty() {
+                            continue;
+                        }
+                        return Some(Ok(chunk));
+                    },
+                    Ok(Ok(None)) => return None,
+                    Ok(Err(e)) => e.to_string(),
+                    Err(_) => "Read Timeout".to_string()
+                },
+                None => match self.connect().await {
+                    Ok(Some(x)) => {
+                        self.response = Some(x);
+                        continue;
+                    },
+                    Ok(None) => return None,
+                    Err(e) => e
+                }
+            };
+            self.response = None;
+            self.attempts += 1;
+            if self.attempts > self.config.retries {
+                log::error!("Download Failed {} at Byte {} : {}", self.url, self.offset, error);
+                return Some(Err(std::io::Error::other(error)));
+            }
+            let delay = self.get_backoff();
+            log::error!("Download Error {} at Byte {} : {}. Retry {} in {:?}", self.url, self.offset, error, self.attempts, delay);
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+pub fn create_url_reader(url:&str, config:DownloadConfig) -> Pin<Box<dyn AsyncRead + Send>> {
+    let client = Client::builder().connect_timeout(config.connect_timeout).build().unwrap_or_default();
+    let download = Download { client, url: url.to_string(), config, response: None, offset: 0, discard: 0, attempts: 0 };
+    let stream = futures::stream::unfold(download, |mut download| async move {
+        download.next_chunk().await.map(|e| (e, download))
+    });
+    Box::pin(StreamReader::new(stream))
+}
+
+
+#[tokio::test]
+async fn test_dropped_connections() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Server which closes every connection after sending 1000 bytes of the data
+    let data:Vec<u8> = (0..5000).map(|e| (e % 251) as u8).collect();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let server_data = data.clone();
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut request = Vec::<u8>::new();
+            let mut buffer = [0u8;1024];
+            while !request.windows(4).any(|e| e == b"\r\n\r\n") {
+                let n = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..n]);
+            }
+            let request = String::from_utf8_lossy(&request).to_lowercase();
+            let start:usize = request.split("range: bytes=").nth(1)
+                .and_then(|e| e.split('-').next()).map(|e| e.parse().unwrap()).unwrap_or(0);
+            let header = if start > 0 {
+                format!("HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\n\r\n", start, server_data.len() - 1, server_data.len(), server_data.len() - start)
+            }
+            else {
+                format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", server_data.len())
+            };
+            let end = std::cmp::min(start + 1000, server_data.len());
+            let _ = socket.write_all(header.as_bytes()).await;
+            let _ = socket.write_all(&server_data[start..end]).await;
+        }
+    });
+
+    let config = DownloadConfig { retries: 2, backoff: Duration::from_millis(1), ..Default::default() };
+    let mut reader = create_url_reader(&format!("http://{}/data.bin", address), config);
+    let mut result = Vec::<u8>::new();
+    reader.read_to_end(&mut result).await.unwrap();
+    assert_eq!(result, data);
+}
@@ -72,13 +72,15 @@ impl TrainingRun {
 
     fn get_data(&mut self, py:Python) -> PyObject {
     
-        let data = self.rx.as_ref().unwrap().recv();
-        let result = match data {
-            Ok(ProviderChannel::Complete) => None,
-            Ok(ProviderChannel::Data(x)) => {
-                Some(py_conversions::convert_data_set(x, py, self.config.dataset_config.clone()))
+        // Messages other than batches are skipped so None is only returned at the end of the data
+        let result = loop {
+            match self.rx.as_ref().unwrap().recv() {
+                Ok(ProviderChannel::Data(x)) => {
+                    break Some(py_conversions::convert_data_set(x, py, self.config.dataset_config.clone()));
+                },
+                Ok(ProviderChannel::Complete) | Err(_) => break None,
+                Ok(_) => continue
             }
-            _ => None
         };
         result.into_py(py)
     }
@@ -0,0 +1,53 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+
+// Seeded random number generators which allow a configuration to reproduce identical batches
+// Each user of randomness derives a separate generator from the training seed, a stream and an index
+// (epoch, batch or sample) so the result doesn't depend on the amount of randomness used elsewhere
+// Without a seed the generators use entropy from the system
+
+#[derive(Debug, Clone, Copy)]
+pub enum RandomStream {
+    Shuffle = 1, // Provider shuffling for each epoch
+    Mixture = 2, // Selection of the mixture components
+    Buffer = 3, // Streaming shuffle buffer
+    Batch = 4, // Seed for each batch created by the batcher
+    Sample = 5, // Masking and span corruption of a sample in the batch
+    Dedup = 6, // MinHash permutations of the dedup stage
+    Files = 7 // Order of the files of a data list for each epoch
+}
+
+// SplitMix64 finalizer used to decorrelate nearby seeds
+fn mix(value:u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+pub fn derive_seed(seed:u64, stream:RandomStream, index:u64) -> u64 {
+    mix(mix(mix(seed) ^ stream as u64) ^ index)
+}
+
+pub fn create_rng(seed:Option<u64>, stream:RandomStream, index:u64) -> StdRng {
+    match seed {
+        Some(x) => StdRng::seed_from_u64(derive_seed(x, stream, index)),
+        None => StdRng::from_entropy()
+    }
+}
+
+// Seed of the batch at index which is used for the sample generators of the batch
+pub fn get_batch_seed(seed:Option<u64>, index:u64) -> Option<u64> {
+    seed.map(|e| derive_seed(e, RandomStream::Batch, index))
+}
+
+
+#[test]
+pub fn test_derived_seeds() {
+    use rand::Rng;
+    let first:Vec<u32> = (0..4).map(|e| create_rng(Some(7), RandomStream::Sample, e).gen()).collect();
+    let second:Vec<u32> = (0..4).map(|e| create_rng(Some(7), RandomStream::Sample, e).gen()).collect();
+    assert_eq!(first, second);
+    assert_ne!(first[0], first[1]);
+    assert_ne!(derive_seed(7, RandomStream::Shuffle, 0), derive_seed(7, RandomStream::Sample, 0));
+}
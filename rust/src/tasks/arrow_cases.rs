@@ -5,21 +5,13 @@ pub fn get_provider(source:HuggingDescription, test:bool) -> ProviderConfig {
     let source = SourceDescription::HuggingFace(source);
 
     if test {
-        ProviderConfig {
-            shuffle: None,
-            flatten: None,
-            length: ProviderLength::Iterations { iterations:1024 },
-            source,
-            filter: None,
-        }
+        ProviderConfig::new(ProviderLength::Iterations { iterations:1024 }, source)
     }
     else {
         ProviderConfig {
             shuffle: Some(true),
             flatten: Some(true),
-            length: ProviderLength::Epochs { epochs:3 },
-            source,
-            filter: None,
+            ..ProviderConfig::new(ProviderLength::Epochs { epochs:3 }, source)
         }
     }
 }
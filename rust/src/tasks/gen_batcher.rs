@@ -6,6 +6,7 @@ use crate::config::ModelType;
 use crate::datasets::dataset::DataSet;
 use crate::datasets::dataset_config::DataSetConfig;
 use crate::tokenizer::tokenizer_wrapper::{TokenizerWrapper};
+use crate::random;
 
 
 
@@ -15,8 +16,11 @@ pub struct GenTokenizer {
     dataset_config:DataSetConfig,
     tokenizer:TokenizerWrapper,
     store:VecDeque<DataSet>, 
+    inputs:VecDeque<Vec<Vec<u32>>>, // Tokens added to each batch in the store which are replayed on resume
     //template:DataSet, 
-    chunk:bool
+    chunk:bool,
+    seed:Option<u64>,
+    batch_count:u64
 }
  
 impl GenTokenizer {
@@ -25,33 +29,43 @@ impl GenTokenizer {
         batch_config:BatchConfig, 
         dataset_config:DataSetConfig,
         tokenizer:TokenizerWrapper,
-        chunk:bool
+        chunk:bool,
+        seed:Option<u64>
     ) -> Self {
         
-        let first_set = model_type.create_dataset(dataset_config.clone(), batch_config.clone(), tokenizer.get_tokenizer_info());
+        let first_set = model_type.create_dataset(dataset_config.clone(), batch_config.clone(), tokenizer.get_tokenizer_info(), random::get_batch_seed(seed, 0));
         Self {
             model_type,
             batch_config,
             dataset_config,
             tokenizer: tokenizer,
             store:VecDeque::from(vec!(first_set)),
+            inputs:VecDeque::from(vec!(vec![])),
             //template:dataset, 
-            chunk:chunk
+            chunk:chunk,
+            seed,
+            batch_count:1
         }
     }
 
+    // Create the next batch with the seed derived from the batch count
+    fn create_dataset(&mut self) -> DataSet {
+        let seed = random::get_batch_seed(self.seed, self.batch_count);
+        self.batch_count += 1;
+        self.model_type.create_dataset(self.dataset_config.clone(), self.batch_config.clone(), self.tokenizer.get_tokenizer_info(), seed)
+    }
+
 
     fn handle_internal_batch(&mut self, ids:&mut [u32]) {
+        self.inputs.back_mut().unwrap().push(ids.to_vec());
         let _result = self.store.back_mut().unwrap().put_full_data(ids.to_vec(), None, None);
         //log::info!("Here {} {}", result, self.store.back().unwrap().done());
         if self.store.back().unwrap().done() {
             let remaining = self.store.back().unwrap().remaining();
             //self.store.push_back(self.template.create_data());
-            let new_data = self.model_type.create_dataset(self.dataset_config.clone(), 
-                self.batch_config.clone(),
-                self.tokenizer.get_tokenizer_info()
-            );
+            let new_data = self.create_dataset();
             self.store.push_back(new_data);
+            self.inputs.push_back(vec![]);
             if remaining.is_some() {
                 let mut r = remaining.unwrap();
                 let l = r.len();
@@ -84,6 +98,7 @@ impl Batcher for GenTokenizer {
             self.handle_internal_batch(&mut ids[0..l]);
         }
        if self.store.front().unwrap().done() {
+            self.inputs.pop_front();
             self.store.pop_front()
        }
        else {
@@ -94,9 +109,25 @@ impl Batcher for GenTokenizer {
     }
 
     fn get_working_batch(&mut self) -> Option<Self::T> {
+        self.inputs.pop_front();
         return self.store.pop_front()
     }
 
+    fn get_carry(&self) -> Vec<Vec<u32>> {
+        self.inputs.iter().flatten().cloned().collect()
+    }
+
+    // Rebuild the partial batches by adding the carried tokens to a new batch with the next seed
+    fn resume(&mut self, batches:u64, carry:Vec<Vec<u32>>) {
+        self.batch_count = batches;
+        let first_set = self.create_dataset();
+        self.store = VecDeque::from(vec!(first_set));
+        self.inputs = VecDeque::from(vec!(vec![]));
+        for mut ids in carry {
+            self.handle_internal_batch(&mut ids);
+        }
+    }
+
 }
 
 
@@ -11,23 +11,12 @@ pub enum MaskingCases {
 // https://dumps.wikimedia.org/other/cirrussearch/20240212/commonswiki-20240212-cirrussearch-content.json.gz 
 pub fn get_provider(test:bool) -> ProviderConfig {
     if test {
-        ProviderConfig {
-            shuffle: None,
-            flatten: None,
-            length: ProviderLength::Iterations { iterations: 10 },
-            source: SourceDescription::DataList(vec![Dataset{location:"../data/test.json.gz".to_string()}]),
-            filter: None,
-        }
+        ProviderConfig::new(ProviderLength::Iterations { iterations: 10 }, SourceDescription::DataList(vec![Dataset{location:"../data/test.json.gz".to_string(), delimiter:None}]))
     }
     else {
-        ProviderConfig {
-            shuffle: None,
-            flatten: None,
-            length: ProviderLength::Epochs { epochs : 1 },
-            //source: SourceDescription::Pile { typ: crate::provider::pile_datasets::PileDatasetType::Total },
-            source: SourceDescription::DataList(vec![Dataset{location:"https://dumps.wikimedia.org/other/cirrussearch/20240212/commonswiki-20240212-cirrussearch-content.json.gz".to_string()}]),
-            filter: None,
-        }
+        ProviderConfig::new(ProviderLength::Epochs { epochs : 1 },
+            //SourceDescription::Pile { typ: crate::provider::pile_datasets::PileDatasetType::Total },
+            SourceDescription::DataList(vec![Dataset{location:"https://dumps.wikimedia.org/other/cirrussearch/20240212/commonswiki-20240212-cirrussearch-content.json.gz".to_string(), delimiter:None}]))
     }
 }
 
@@ -57,7 +46,8 @@ pub fn get_case(typ:MaskingCases, test:bool) -> TrainingConfig {
                 batch, 
                 transport: arrow_cases::get_transport_config(test), 
                 node: NodeConfig::None, 
-                dataset_config: DataSetConfig::Mask { mask_length , mask: 103}
+                dataset_config: DataSetConfig::Mask { mask_length , mask: 103},
+                seed: None
             }
         },
         MaskingCases::Gpt => {            
@@ -72,7 +62,8 @@ pub fn get_case(typ:MaskingCases, test:bool) -> TrainingConfig {
                 batch, 
                 transport: arrow_cases::get_transport_config(test), 
                 node: NodeConfig::None, 
-                dataset_config: DataSetConfig::Gpt 
+                dataset_config: DataSetConfig::Gpt,
+                seed: None
             }
         },
         MaskingCases::T5 => {
@@ -86,7 +77,8 @@ pub fn get_case(typ:MaskingCases, test:bool) -> TrainingConfig {
                 batch, 
                 transport: arrow_cases::get_transport_config(test), 
                 node: NodeConfig::None, 
-                dataset_config:DataSetConfig::Span{ avg_span_gap: 16.0, avg_span_size: 2.0 } 
+                dataset_config:DataSetConfig::Span{ avg_span_gap: 16.0, avg_span_size: 2.0 },
+                seed: None
             }
         },
     }
@@ -3,7 +3,7 @@ use std::sync::mpsc::SyncSender;
 
 use tokio::task::{JoinHandle, self};
 
-use crate::{provider::{ProviderChannel, general_file_provider, pile_datasets, source_filter::SourceFilter, provider_config::{ProviderConfig, SourceDescription}}, tasks::{runner_simple}, datasets::{dataset::DataSet}, tokenizer::tokenizer_wrapper::{self}, config::{TrainingConfig}};
+use crate::{provider::{ProviderChannel, general_file_provider, mixture_provider, stream_provider, pile_datasets, arrow_transfer::ArrowTransfer, column_generator::TextArrowGenerator, source_filter::SourceFilter, provider_config::{ProviderConfig, SourceDescription}}, tasks::{runner_simple}, datasets::{dataset::DataSet}, tokenizer::tokenizer_wrapper::{self}, config::{TrainingConfig}};
 use tokio::sync::mpsc::Sender;
 
 use super::{masking_test_endpoint::MaskingEndpoint};
@@ -17,20 +17,22 @@ pub fn create_provider(provider_config:ProviderConfig, tx:Sender<ProviderChannel
 
 
     //let provider_config:ProviderConfig = serde_yaml::from_value(value["source"].to_owned()).unwrap();
-    let filter = provider_config.filter.unwrap_or(SourceFilter::JsonText);
+    let filter = provider_config.filter.clone().unwrap_or(SourceFilter::JsonText);
     
     let handle = task::spawn(
         async move {
-            match provider_config.source {
+            match provider_config.source.clone() {
                 SourceDescription::DataList(datasets) => {
                     //log::info!("Datasets {:?}", datasets);
-                    general_file_provider::load_data_sets(datasets, provider_config.length, tx, cache, &filter).await;
+                    let order = provider_config.get_dataset_order();
+                    general_file_provider::load_data_sets(datasets, provider_config.length, tx, cache, &filter, provider_config.resume, order).await;
                 },
                 SourceDescription::Pile{typ} => {
                     let datasets = pile_datasets::get_datasets(typ);
                     match datasets {
                         Some(x) => {
-                            general_file_provider::load_data_sets(x, provider_config.length, tx, cache, &filter).await;
+                            let order = provider_config.get_dataset_order();
+                            general_file_provider::load_data_sets(x, provider_config.length, tx, cache, &filter, provider_config.resume, order).await;
                         }
                         None => {
                             log::error!("Data Set Not Supported");
@@ -39,7 +41,31 @@ pub fn create_provider(provider_config:ProviderConfig, tx:Sender<ProviderChannel
                     }
                     
                 },
-         
+                SourceDescription::Arrow(location) | SourceDescription::Parquet(location) => {
+                    let extension = if let SourceDescription::Parquet(_) = &provider_config.source {".parquet"} else {".arrow"};
+                    let mut loader = match ArrowTransfer::<String>::from_local(&location, extension) {
+                        Some(x) => x,
+                        None => std::process::exit(1)
+                    };
+                    let column = provider_config.columns.as_ref().map(|e| e.text.clone()).unwrap_or_else(|| "text".to_string());
+                    match TextArrowGenerator::new(&loader.schema, &column) {
+                        Ok(x) => loader.generator = Some(Box::new(x)),
+                        Err(e) => {
+                            log::error!("{}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                    loader.load_data(provider_config, tx).await;
+                },
+                SourceDescription::Mixture(description) => {
+                    mixture_provider::load_mixture(description, provider_config, tx, cache, create_provider).await;
+                },
+                SourceDescription::Stdin => {
+                    stream_provider::load_stream(None, provider_config.length, tx, &filter, provider_config.resume).await;
+                },
+                SourceDescription::Fifo(path) => {
+                    stream_provider::load_stream(Some(path), provider_config.length, tx, &filter, provider_config.resume).await;
+                },
                 _ => {
                     log::error!("Can't support Input Type");
                 }
@@ -57,7 +83,8 @@ fn create_generator(config:TrainingConfig)-> Box<dyn crate::batcher::Batcher<S=S
         config.batch, 
         config.dataset_config,  
         tokenizer_wrapper::get_tokenizer(config.tokenizer).unwrap(), 
-        true);
+        true,
+        config.seed);
     Box::new(wrap)
 }
 
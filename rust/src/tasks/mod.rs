@@ -29,7 +29,7 @@ pub struct DatasetInfo {
 
 pub async fn run(config:TrainingConfig, task:TaskType, cache:Option<String>, destination:Option<SyncSender<ProviderChannel<DataSet>>>) -> bool{
     match task {
-        TaskType::Squad => single_class::runner::run(config).await,
+        TaskType::Squad => squad::squad_runner::run(config).await,
         TaskType::MultiLabel => single_class::runner::run(config).await,
         TaskType::SingleClass => single_class::runner::run(config).await,
         TaskType::Mlm => masking::masking_runner::run(config, destination, cache).await,
@@ -1,4 +1,3 @@
-pub mod multi_arrow;
 pub mod multi_cases;
 
 use serde::{Serialize, Deserialize};
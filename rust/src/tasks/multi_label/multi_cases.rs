@@ -30,7 +30,8 @@ pub fn get_case(test:bool) -> TrainingConfig {
         batch, 
         transport:arrow_cases::get_transport_config(test), 
         node: NodeConfig::None, 
-        dataset_config: DataSetConfig::MultiLabel{number_labels: 9}
+        dataset_config: DataSetConfig::MultiLabel{number_labels: 9},
+        seed: None
     }
         
 }
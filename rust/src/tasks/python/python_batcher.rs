@@ -4,6 +4,7 @@ use crate::batcher::{Batcher, BatchConfig};
 use crate::config::ModelType;
 use crate::datasets::dataset::DataSet;
 use crate::datasets::dataset_config::DataSetConfig;
+use crate::random;
 
 
 use super::python_runner::PythonTokenizer;
@@ -15,7 +16,9 @@ pub struct PythonBatch {
     dataset_config:DataSetConfig,
     batch_config:BatchConfig,
     tokenizer:PythonTokenizer,
-    store:VecDeque<DataSet>
+    store:VecDeque<DataSet>,
+    seed:Option<u64>,
+    batch_count:u64
 }
  
 impl PythonBatch {
@@ -23,17 +26,27 @@ impl PythonBatch {
         dataset_config:DataSetConfig,
         batch_config:BatchConfig, 
         tokenizer:PythonTokenizer,
+        seed:Option<u64>
     ) -> Self {
         
-        let first_set = model_type.create_dataset(dataset_config.clone(), batch_config.clone(), tokenizer.get_tokenizer_info());
+        let first_set = model_type.create_dataset(dataset_config.clone(), batch_config.clone(), tokenizer.get_tokenizer_info(), random::get_batch_seed(seed, 0));
         Self {
             model_type,
             dataset_config,
             batch_config:batch_config,
             tokenizer: tokenizer,
-            store:VecDeque::from(vec!(first_set))
+            store:VecDeque::from(vec!(first_set)),
+            seed,
+            batch_count:1
         }
     }
+
+    // Create the next batch with the seed derived from the batch count
+    fn create_dataset(&mut self) -> DataSet {
+        let seed = random::get_batch_seed(self.seed, self.batch_count);
+        self.batch_count += 1;
+        self.model_type.create_dataset(self.dataset_config.clone(), self.batch_config.clone(), self.tokenizer.get_tokenizer_info(), seed)
+    }
 }
     
 impl Batcher for PythonBatch {
@@ -45,14 +58,12 @@ impl Batcher for PythonBatch {
         let ids = self.tokenizer.encode(data);
         match self.store.back() {
             Some(x) => if x.done() {
-                self.store.push_back(self.model_type.create_dataset(self.dataset_config.clone(), 
-                self.batch_config.clone(),
-                self.tokenizer.get_tokenizer_info()));
+                let data = self.create_dataset();
+                self.store.push_back(data);
             },
             None => {
-                self.store.push_back(self.model_type.create_dataset(self.dataset_config.clone(), 
-                self.batch_config.clone(),
-                self.tokenizer.get_tokenizer_info()));
+                let data = self.create_dataset();
+                self.store.push_back(data);
             }
         }
         
@@ -86,6 +97,12 @@ impl Batcher for PythonBatch {
         return self.store.pop_front()
     }
 
+    fn resume(&mut self, batches:u64, _carry:Vec<Vec<u32>>) {
+        self.batch_count = batches;
+        let first_set = self.create_dataset();
+        self.store = VecDeque::from(vec!(first_set));
+    }
+
 }
 
 
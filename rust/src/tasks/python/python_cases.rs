@@ -17,9 +17,8 @@ fn get_provider(test:bool) -> ProviderConfig {
     ProviderConfig {
         shuffle: Some(true),
         flatten: Some(true),
-        length,
-        source: SourceDescription::Pile { typ:PileDatasetType::GithubDataset },
         filter: Some(SourceFilter::PythonText),
+        ..ProviderConfig::new(length, SourceDescription::Pile { typ:PileDatasetType::GithubDataset })
     } 
 }
 
@@ -47,7 +46,8 @@ pub fn get_case(case:Cases, test:bool) -> TrainingConfig {
                 batch: batch_config, 
                 transport: arrow_cases::get_transport_config(test), 
                 node: NodeConfig::None, 
-                dataset_config
+                dataset_config,
+                seed: None
             }
         }
         Cases::Basic => {
@@ -59,7 +59,8 @@ pub fn get_case(case:Cases, test:bool) -> TrainingConfig {
                 batch: batch_config, 
                 transport: arrow_cases::get_transport_config(test), 
                 node: NodeConfig::None, 
-                dataset_config
+                dataset_config,
+                seed: None
             }
         },
         Cases::Context => {
@@ -71,7 +72,8 @@ pub fn get_case(case:Cases, test:bool) -> TrainingConfig {
                 batch: batch_config, 
                 transport: arrow_cases::get_transport_config(test), 
                 node: NodeConfig::None, 
-                dataset_config
+                dataset_config,
+                seed: None
             }
         }
     
@@ -47,13 +47,13 @@ fn create_generator(config:TrainingConfig)-> Box<dyn crate::batcher::Batcher<S=S
         }
     };
     let tokenizer = PythonParserNew::new(context_size);
-    let batch = PythonBatch::new( config.model_config, config.dataset_config, config.batch, PythonTokenizer::Run(tokenizer));
+    let batch = PythonBatch::new( config.model_config, config.dataset_config, config.batch, PythonTokenizer::Run(tokenizer), config.seed);
     Box::new(batch)
 }
 
 fn create_context_generator(config:TrainingConfig)-> Box<dyn crate::batcher::Batcher<S=String,T=DataSet> + Send> {
     let tokenizer = PythonContextCreator::new(2048);
-    let batch = PythonBatch::new( config.model_config, config.dataset_config, config.batch, PythonTokenizer::Context(tokenizer));
+    let batch = PythonBatch::new( config.model_config, config.dataset_config, config.batch, PythonTokenizer::Context(tokenizer), config.seed);
     Box::new(batch)
 }
 
@@ -17,7 +17,7 @@ use crate::datasets::dataset_config::DataSetConfig;
 
 use crate::provider::provider_config::ProviderConfig;
 use crate::provider::arrow_transfer::{ArrowTransfer};
-use crate::provider::{ProviderChannel};
+use crate::provider::{ProviderChannel, DocumentText, shuffle_buffer, dedup, quality_filter};
 use crate::transport::{self};
 
 
@@ -45,10 +45,11 @@ pub async fn create_tokenizer<P:Send + 'static, D:Serialize+Send+'static>(config
     
     
     //let tokenizer = tokenizer_wrapper::get_tokenizer(config.tokenizer).unwrap();
+    let resume = config.source.resume.clone();
     let generator = generator(config);
 
     let join_tokenizer = task::spawn(async move {
-        let result = batcher::create_batch(rx, tx, generator);
+        let result = batcher::create_batch(rx, tx, generator, resume);
         result.await;
     });
     join_tokenizer
@@ -65,8 +66,8 @@ type DataProviderAsync<P> = Box<dyn Fn(ProviderConfig, Sender<ProviderChannel<P>
 type DataProviderSync<P> = Box<dyn Fn(&ProviderConfig, DataSetConfig) -> ArrowTransfer<P>>;
 
 // TODO : Clean up the direct reading of the Serde Value and use a serde load to a struct
-pub async fn run_main<'de, P:Clone + Send + 'static>(
-    config:TrainingConfig,
+pub async fn run_main<'de, P:Clone + Send + DocumentText + 'static>(
+    mut config:TrainingConfig,
     base_provider:ProviderType<DataProviderAsync<P>,DataProviderSync<P>>,
     generator:Box<dyn Fn(TrainingConfig)-> Box<dyn Batcher<S=P,T=DataSet> + Send>>,
     destination:Option<SyncSender<ProviderChannel<DataSet>>>,
@@ -74,6 +75,10 @@ pub async fn run_main<'de, P:Clone + Send + 'static>(
     ) -> bool {
 
 
+    // The training seed controls the shuffling of the provider
+    if config.seed.is_some() {
+        config.source.seed = config.seed;
+    }
     let config_copy = config.clone();
 
     // Create the Channel from Input to Tokenizer
@@ -82,6 +87,19 @@ pub async fn run_main<'de, P:Clone + Send + 'static>(
     let (tx_trans, rx_trans) = tokio::sync::mpsc::channel::<ProviderChannel<DataSet>>(1);
 
     // Data Loading Configuration
+    let shuffle_config = config.source.get_shuffle_buffer();
+    let quality_config = config.source.quality.clone();
+    if let Some(x) = &quality_config {
+        log::info!("Quality Filter {:?}", x);
+    }
+    let quality_filter = match quality_config.map(quality_filter::QualityFilter::new).transpose() {
+        Ok(x) => x,
+        Err(e) => {
+            log::error!("{}", e);
+            return false;
+        }
+    };
+    let dedup_config = config.source.dedup.clone();
 
     // Create the Data Provider Configuration
     let join_provider = match base_provider {
@@ -89,6 +107,33 @@ pub async fn run_main<'de, P:Clone + Send + 'static>(
         ProviderType::Async(y) => create_data_provider(config.source, config.dataset_config.clone(),y, tx).await,
     };
 
+    // Optional quality filters and near duplicate filtering of the provider output
+    let (rx, join_quality) = match quality_filter {
+        Some(x) => {
+            let (rx_quality, handle) = quality_filter::create_quality_filter(x, rx);
+            (rx_quality, Some(handle))
+        },
+        None => (rx, None)
+    };
+    let (rx, join_dedup) = match dedup_config {
+        Some(x) => {
+            log::info!("Dedup {:?}", x);
+            let (rx_dedup, handle) = dedup::create_dedup(x, rx);
+            (rx_dedup, Some(handle))
+        },
+        None => (rx, None)
+    };
+
+    // Optional shuffle buffer between the provider and the batcher
+    let (rx, join_shuffle) = match shuffle_config {
+        Some(x) => {
+            log::info!("Shuffle Buffer {:?}", x);
+            let (rx_shuffle, handle) = shuffle_buffer::create_shuffle_buffer(x, rx);
+            (rx_shuffle, Some(handle))
+        },
+        None => (rx, None)
+    };
+
     // Create the batcher
     let join_tokenizer = create_tokenizer(config_copy.clone(),
         generator, 
@@ -105,6 +150,15 @@ pub async fn run_main<'de, P:Clone + Send + 'static>(
 
 
     let result = tokio::join!(join_rx, join_tokenizer, join_provider, join_node);
+    if let Some(x) = join_shuffle {
+        let _ = x.await;
+    }
+    if let Some(x) = join_dedup {
+        let _ = x.await;
+    }
+    if let Some(x) = join_quality {
+        let _ = x.await;
+    }
     log::info!("Finished : Internal-{:?} External-{:?}", result.0, result.3);
     return result.0.unwrap() && result.3.unwrap();
     
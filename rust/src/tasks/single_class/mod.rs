@@ -1,4 +1,3 @@
-pub mod single_arrow;
 pub mod runner;
 pub mod single_cases;
 
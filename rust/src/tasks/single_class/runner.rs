@@ -1,56 +1,54 @@
 
-use crate::{provider::{arrow_transfer::ArrowTransfer, arrow_provider::{create_hugging_description}, provider_config::{SourceDescription, ProviderConfig}}, tasks::{ runner_simple, multi_label::multi_arrow::MultiArrowGenerator}, tokenizer::tokenizer_wrapper::{self}, config::{TrainingConfig}, datasets::{dataset::DataSet, dataset_config::DataSetConfig}, models::{simple_batcher, simple_transport::SimpleTransport}};
+use crate::{provider::{arrow_transfer::ArrowTransfer, column_generator::ColumnArrowGenerator, provider_config::{ProviderConfig, ColumnConfig, LabelType}}, tasks::{runner_simple}, tokenizer::tokenizer_wrapper::{self}, config::{TrainingConfig}, datasets::{dataset::DataSet, dataset_config::DataSetConfig}, models::{simple_batcher, simple_transport::SimpleTransport}};
 
-use super::{single_arrow::SingleClassArrowGenerator};
 
+// Default column names of the task which are used without a configured column mapping
+fn default_columns(data_config:DataSetConfig) -> Option<ColumnConfig> {
+    match data_config {
+        DataSetConfig::MultiLabel { number_labels:_ } => Some(ColumnConfig::new("sentence", None, Some("labels"), Some(LabelType::Multi))),
+        DataSetConfig::SingleClass => Some(ColumnConfig::new("text", None, Some("label"), Some(LabelType::Single))),
+        _ => None
+    }
+}
 
-// Create the Dataset Provider for Squad
+// Create the Dataset Provider with the generator which converts the arrow data into the transport format for the task
+// The configured column mapping takes priority over the default column names
 fn create_provider(config:&ProviderConfig, data_config:DataSetConfig) -> ArrowTransfer<SimpleTransport>{
-    match &config.source {
-        SourceDescription::HuggingFace(x) => {
-            let arrow_description = create_hugging_description(x.dataset.clone(), x.args.clone(), x.operations[0].clone());
-            let mut loader = ArrowTransfer::new(arrow_description.0, arrow_description.1);
-            match data_config {
-                DataSetConfig::MultiLabel { number_labels:_ } => {
-                    loader.generator = Some(Box::new(MultiArrowGenerator::new(&loader.schema)));
-                },
-                DataSetConfig::SingleClass => {
-                    loader.generator = Some(Box::new(SingleClassArrowGenerator::new(&loader.schema)));
-                },
-                _ => {
-                    log::error!("Configuration Not Supported"); 
-                    std::process::exit(1);
-                }
-            };
-            return loader
-            //let generator = Box::new(SingleClassArrowGenerator::new(&loader.schema)) ;
-    
-            //loader.generator = Some(generator);
-            //return loader;
-        },
-        SourceDescription::Arrow(_) => todo!(),
-        _ => { 
+    let columns = match config.columns.clone().or_else(|| default_columns(data_config)) {
+        Some(x) => x,
+        None => {
             log::error!("Configuration Not Supported");
             std::process::exit(1);
         }
-    };    
+    };
+    let mut loader = match ArrowTransfer::from_config(config) {
+        Some(x) => x,
+        None => std::process::exit(1)
+    };
+    match ColumnArrowGenerator::new(&loader.schema, &columns) {
+        Ok(x) => loader.generator = Some(Box::new(x)),
+        Err(e) => {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    loader
 }
 
-// Create the Batcher for Squad
+// Create the Batcher for the classification tasks
 fn create_generator(config:TrainingConfig)-> Box<dyn crate::batcher::Batcher<S=SimpleTransport,T=DataSet> + Send> {
 
     //let tokenizer = tokenizer_wrapper::get_tokenizer(config.tokenizer).unwrap();
     let batcher = simple_batcher::SimpleBatcher::new(config.model_config,
         config.dataset_config, 
         config.batch, 
-        tokenizer_wrapper::get_tokenizer(config.tokenizer).unwrap());
+        tokenizer_wrapper::get_tokenizer(config.tokenizer).unwrap(),
+        config.seed);
     Box::new(batcher)
     
 }
 
 
-// TODO : The squad implementation has quite a few flaws and is not fully functional
-
 pub async fn run(config:TrainingConfig) -> bool{
     let result = runner_simple::run_main(config,
         runner_simple::ProviderType::Async(Box::new(create_provider)), 
@@ -36,7 +36,8 @@ pub fn get_case(typ:Cases, test:bool) -> TrainingConfig {
                 batch, 
                 transport:arrow_cases::get_transport_config(test), 
                 node: NodeConfig::None, 
-                dataset_config:DataSetConfig::SingleClass
+                dataset_config:DataSetConfig::SingleClass,
+                seed: None
             }
         }
         
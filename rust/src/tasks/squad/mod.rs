@@ -3,6 +3,7 @@ pub mod squad_arrow;
 pub mod squad_tokenizer;
 pub mod squad_endpoint;
 pub mod squad_cases;
+pub mod squad_runner;
 
 
 use serde::{Serialize, Deserialize};
@@ -1,33 +1,55 @@
 
 
 use std::{sync::Arc};
-use arrow::{array::{StringArray, StructArray, Int32Array, ListArray}, datatypes::Schema};
+use arrow::{array::{Array, StringArray, StructArray, Int32Array, ListArray}, datatypes::{Schema, DataType}};
 
-use crate::provider::arrow_transfer::ArrowGenerator;
+use crate::provider::{arrow_transfer::ArrowGenerator, column_generator, provider_config::ColumnConfig};
 
 use super::squad_data::SquadGeneral;
 
 pub struct SquadArrowGenerator {
     
-    pub q:usize,
-    pub c:usize,
-    pub a:usize,
+    pub q:usize, // Question Location
+    pub c:usize, // Context Location
+    pub a:usize, // Answers Location
 }
 
+// Answers are a struct with a list of the answer texts and a list of the start positions
+fn is_answers(typ:&DataType) -> bool {
+    match typ {
+        DataType::Struct(fields) => {
+            let text = fields.iter().find(|e| e.name() == "text").map(|e| is_text_list(e.data_type()));
+            let start = fields.iter().find(|e| e.name() == "answer_start").map(|e| is_int_list(e.data_type()));
+            text.unwrap_or(false) && start.unwrap_or(false)
+        },
+        _ => false
+    }
+}
+
+fn is_text_list(typ:&DataType) -> bool {
+    matches!(typ, DataType::List(x) if x.data_type() == &DataType::Utf8)
+}
 
+fn is_int_list(typ:&DataType) -> bool {
+    matches!(typ, DataType::List(x) if x.data_type() == &DataType::Int32)
+}
 
 impl ArrowGenerator for SquadArrowGenerator {
     type T = SquadGeneral;
-    fn get_data(&self, data:&arrow::record_batch::RecordBatch) -> SquadGeneral {
-        let question = StringArray::from(data.slice(0,1).column(self.q).data().to_owned()).value(0).to_string();
-        let context = StringArray::from(data.slice(0,1).column(self.c).data().to_owned()).value(0).to_string();
+    fn get_data(&self, data:&arrow::record_batch::RecordBatch) -> Option<SquadGeneral> {
+        let question = column_generator::get_text(data.column(self.q), 0)?;
+        let context = column_generator::get_text(data.column(self.c), 0)?;
         
         let answers = StructArray::from(data.slice(0,1).column(self.a).data().to_owned());
-        let answer_list = ListArray::from(answers.column(0).data().to_owned()).value(0);
+        let answer_list = ListArray::from(answers.column_by_name("text")?.data().to_owned()).value(0);
+        let sp_list = ListArray::from(answers.column_by_name("answer_start")?.data().to_owned()).value(0);
+        // Questions without an answer (squad_v2) are skipped
+        if answer_list.is_empty() || sp_list.is_empty() {
+            return None;
+        }
         let answer = StringArray::from(answer_list.data().to_owned()).value(0).to_string();
 
         // TODO : The start and end pointers don't properly work. I believe it's due to the character 
-        let sp_list = ListArray::from(answers.column(1).data().to_owned()).value(0);
         let sp = Int32Array::from(sp_list.data().to_owned()).value(0);
         let ep = sp + answer.len() as i32;
 
@@ -42,19 +64,68 @@ impl ArrowGenerator for SquadArrowGenerator {
         
 
         let squad_data = SquadGeneral{ question: question, context: context, sp: sp as u32, ep: ep as u32, answer:Some(answer), offset:offset };
-        return squad_data;
+        return Some(squad_data);
     }
 }
 
 impl SquadArrowGenerator {
 
-    pub fn new(schema:&Arc<Schema>) -> Self {
-        Self {
-            q: schema.column_with_name("question").unwrap().0,
-            c: schema.column_with_name("context").unwrap().0,
-            a: schema.column_with_name("answers").unwrap().0,
-        }
+    // Columns of the HuggingFace squad dataset
+    pub fn default_columns() -> ColumnConfig {
+        ColumnConfig::new("question", Some("context"), Some("answers"), None)
+    }
+
+    // The text column is the question, the pair column the context and the label column the answers
+    pub fn new(schema:&Arc<Schema>, config:&ColumnConfig) -> Result<Self, String> {
+        let context = config.pair.as_ref().ok_or("Squad Requires a Context Column".to_string())?;
+        let answers = config.label.as_ref().ok_or("Squad Requires an Answers Column".to_string())?;
+        Ok(Self {
+            q: column_generator::get_column(schema, &config.text, column_generator::is_text)?,
+            c: column_generator::get_column(schema, context, column_generator::is_text)?,
+            a: column_generator::get_column(schema, answers, is_answers)?,
+        })
     }
 
     
-}
\ No newline at end of file
+}
+
+
+#[test]
+pub fn test_squad_columns() {
+    use arrow::{datatypes::Field, array::{ArrayRef, ListBuilder, StringBuilder, Int32Builder}, record_batch::RecordBatch};
+
+    let mut text = ListBuilder::new(StringBuilder::new());
+    text.values().append_value("answer");
+    text.append(true);
+    let mut start = ListBuilder::new(Int32Builder::new());
+    start.values().append_value(4);
+    start.append(true);
+    // The second question doesn't have an answer
+    text.append(true);
+    start.append(true);
+    let (text, start) = (Arc::new(text.finish()) as ArrayRef, Arc::new(start.finish()) as ArrayRef);
+    let answers = StructArray::from(vec![
+        (Field::new("text", text.data_type().clone(), true), text),
+        (Field::new("answer_start", start.data_type().clone(), true), start),
+    ]);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("answers", answers.data_type().clone(), false),
+        Field::new("context", DataType::Utf8, false),
+        Field::new("query", DataType::Utf8, false),
+    ]));
+    let batch = RecordBatch::try_new(schema.clone(), vec![
+        Arc::new(StringArray::from(vec!["0", "1"])),
+        Arc::new(answers),
+        Arc::new(StringArray::from(vec!["the answer", "no answer"])),
+        Arc::new(StringArray::from(vec!["question", "unanswerable"])),
+    ]).unwrap();
+
+    // The columns are found by name in any order
+    assert!(SquadArrowGenerator::new(&schema, &SquadArrowGenerator::default_columns()).is_err());
+    let generator = SquadArrowGenerator::new(&schema, &ColumnConfig::new("query", Some("context"), Some("answers"), None)).unwrap();
+    let data = generator.get_data(&batch.slice(0, 1)).unwrap();
+    assert_eq!((data.question.as_str(), data.context.as_str(), data.sp, data.ep), ("question", "the answer", 4, 10));
+    assert!(generator.get_data(&batch.slice(1, 1)).is_none());
+    assert!(SquadArrowGenerator::new(&schema, &ColumnConfig::new("query", Some("context"), Some("id"), None)).is_err());
+}
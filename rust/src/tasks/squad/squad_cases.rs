@@ -5,7 +5,7 @@ use crate::{config::{TrainingConfig, ModelType}, tokenizer::tokenizer_config::{T
 
 pub fn get_provider(test:bool) -> ProviderConfig {
 
-    let source = HuggingDescription::new("squad",None,vec![""]);
+    let source = HuggingDescription::new("squad",None,vec!["train"]);
     arrow_cases::get_provider(source, test)
 
 }
@@ -31,7 +31,8 @@ pub fn get_case(test:bool) -> TrainingConfig {
         batch, 
         transport:arrow_cases::get_transport_config(test), 
         node: NodeConfig::None, 
-        dataset_config:DataSetConfig::Squad
+        dataset_config:DataSetConfig::Squad,
+        seed: None
     }
         
 }
@@ -0,0 +1,41 @@
+use crate::{provider::{arrow_transfer::ArrowTransfer, provider_config::ProviderConfig}, tasks::runner_simple, tokenizer::tokenizer_wrapper, config::TrainingConfig, datasets::{dataset::DataSet, dataset_config::DataSetConfig}, batcher::Batcher};
+
+use super::{squad_arrow::SquadArrowGenerator, squad_data::{SquadData, SquadGeneral}, squad_tokenizer::SquadTokenizer};
+
+
+// Create the Dataset Provider for Squad
+// The configured column mapping selects the question (text), context (pair) and answers (label) columns
+fn create_provider(config:&ProviderConfig, _data_config:DataSetConfig) -> ArrowTransfer<SquadGeneral> {
+    let columns = config.columns.clone().unwrap_or_else(SquadArrowGenerator::default_columns);
+    let mut loader = match ArrowTransfer::from_config(config) {
+        Some(x) => x,
+        None => std::process::exit(1)
+    };
+    match SquadArrowGenerator::new(&loader.schema, &columns) {
+        Ok(x) => loader.generator = Some(Box::new(x)),
+        Err(e) => {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    loader
+}
+
+// Create the Batcher for Squad
+fn create_generator(config:TrainingConfig) -> Box<dyn Batcher<S=SquadGeneral,T=DataSet> + Send> {
+    let tokenizer = tokenizer_wrapper::get_tokenizer(config.tokenizer).unwrap();
+    Box::new(SquadTokenizer::new(SquadData::new(config.batch), tokenizer))
+}
+
+
+// TODO : The squad implementation has quite a few flaws and is not fully functional
+
+pub async fn run(config:TrainingConfig) -> bool{
+    let result = runner_simple::run_main(config,
+        runner_simple::ProviderType::Async(Box::new(create_provider)), 
+        Box::new(create_generator), 
+        None,
+        None);
+
+    result.await 
+}
@@ -1,5 +1,5 @@
 
-use crate::batcher::Batcher;
+use crate::{batcher::Batcher, datasets::dataset::DataSet};
 use crate::tokenizer::tokenizer_wrapper::{TokenizerWrapper};
 
 
@@ -22,9 +22,9 @@ impl SquadTokenizer {
 
     impl Batcher for SquadTokenizer {
         type S = SquadGeneral;
-        type T = SquadData;
+        type T = DataSet;
 
-        fn create_sync_batch(&mut self, data:SquadGeneral) -> Option<SquadData> {
+        fn create_sync_batch(&mut self, data:SquadGeneral) -> Option<DataSet> {
             
             //let result = self.tokenizer.get_tokenizer().encode((data.question.clone(), data.context.clone()), true).unwrap();
             let input_data = tokenizers::EncodeInput::Dual(data.question.clone().into(), data.context.clone().into());
@@ -41,7 +41,7 @@ impl SquadTokenizer {
            
             let mut old_batch = self.batch.new_data(); 
             std::mem::swap(&mut self.batch, &mut old_batch);
-            return Some(old_batch);
+            return Some(DataSet::Squad(old_batch));
         }
         
     }
@@ -23,7 +23,7 @@ pub async fn transport<DataSet>(tx:SyncSender<Option<DataSet>>,
             Some(ProviderChannel::Complete) => {
                 let _ = tx.send(None);
             },
-            Some(ProviderChannel::Info(_)) => {},
+            Some(ProviderChannel::Info(_)) | Some(ProviderChannel::Position(_)) | Some(ProviderChannel::Checkpoint(_)) | Some(ProviderChannel::Error(_)) => {},
             Some(ProviderChannel::Data(x)) => {
                 let _ = tx.send(Some(x));
             }
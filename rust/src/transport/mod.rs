@@ -76,6 +76,8 @@ pub async fn create_transport(config:TrainingConfig,
                         }
                     }
                     Some(ProviderChannel::Info(_x)) => {},
+                    // The python interface only takes batches so the checkpoints stay in this task
+                    Some(ProviderChannel::Checkpoint(_x)) => {},
                     _ => {log::error!("Failed Data");},
                 };
             }
@@ -40,7 +40,7 @@ pub async fn receive<T>(
         ProviderChannel::Data(x) => {
             _data = x;
         },
-        ProviderChannel::Info(_) => {},
+        _ => {},
     }
     
     // Wait for the rest of the inputs to flush out to exit
@@ -49,7 +49,7 @@ pub async fn receive<T>(
         let result = rx.recv().await; //.unwrap();
         
         match result {
-            Some(ProviderChannel::Info(_)) => {
+            Some(ProviderChannel::Info(_)) | Some(ProviderChannel::Position(_)) | Some(ProviderChannel::Checkpoint(_)) | Some(ProviderChannel::Error(_)) => {
                 continue;
             }   
             Some(ProviderChannel::Complete) => {
@@ -4,7 +4,7 @@
 use serde::{Serialize};
 use tokio::sync::mpsc::Receiver;
 
-use crate::{provider::ProviderChannel, config::TrainingConfig};
+use crate::{provider::ProviderChannel, config::TrainingConfig, checkpoint::DataCheckpoint};
 
 
 
@@ -42,6 +42,9 @@ pub async fn receive_transport<T:Serialize>(address:String,
     };
 
     let mut packet_count = 0;
+    // Checkpoint of the last delivered batch and the checkpoint for the next batch
+    let mut checkpoint:Option<DataCheckpoint> = training_config.source.resume.clone();
+    let mut pending:Option<DataCheckpoint> = None;
     loop {
         let mut msg = zmq::Message::new();
         let _ = socket.recv(&mut msg, 0);
@@ -55,12 +58,22 @@ pub async fn receive_transport<T:Serialize>(address:String,
                 let result = serde_pickle::to_vec(&dataset_info, Default::default());
                 let _ = socket.send(result.unwrap(), 0);
             },
+            Some("Checkpoint") => {
+                let result = serde_pickle::to_vec(&checkpoint, Default::default());
+                let _ = socket.send(result.unwrap(), 0);
+            },
             Some("Data") => {
-                let data = rx.recv().await;
-                match data.unwrap() {
-                    ProviderChannel::Info(x) => {
-                        println!("Getting Dataset Information {:?}", x);
+                // Wait for the next batch while storing the checkpoint sent before it
+                let mut data = rx.recv().await;
+                while let Some(ProviderChannel::Info(_)) | Some(ProviderChannel::Position(_)) | Some(ProviderChannel::Checkpoint(_)) = data {
+                    match data.unwrap() {
+                        ProviderChannel::Info(x) => println!("Getting Dataset Information {:?}", x),
+                        ProviderChannel::Checkpoint(x) => pending = Some(x),
+                        _ => {}
                     }
+                    data = rx.recv().await;
+                }
+                match data.unwrap() {
                     ProviderChannel::Complete => {
                         log::info!("Finished Transport");
                         let _ = socket.send("Finished", 0);
@@ -70,11 +83,15 @@ pub async fn receive_transport<T:Serialize>(address:String,
                     ProviderChannel::Data(x) => {
                         let result = serde_pickle::to_vec(&x, Default::default());
                         let _ = socket.send(result.unwrap(), 0);
+                        if pending.is_some() {
+                            checkpoint = pending.take();
+                        }
                         packet_count += 1;
                         if packet_count % 64 == 1 {
                             log::info!("Sent Packet {:?}", packet_count);
                         }
                     },
+                    _ => {}
                 }
             }
             _ => {
@@ -62,3 +62,21 @@ fn test_t5_python() {
 }
 
 
+
+#[test]
+fn test_config_file() {
+    let overrides = vec!["batch.batch_size=4".to_string(), "source.length.iterations=20".to_string()];
+    let config = TrainingConfig::load("tests/masking.yaml", &overrides).unwrap();
+    assert_eq!(config.batch.batch_size, 4);
+    assert!(matches!(config.source.length, loader::provider::provider_config::ProviderLength::Iterations { iterations: 20 }));
+    assert!(config.validate(&TaskType::Mlm).is_ok());
+    assert!(config.validate(&TaskType::SingleClass).is_err());
+
+    // HuggingFace sources without an operation use the train split
+    let mut config = loader::tasks::squad::squad_cases::get_case(true);
+    if let loader::provider::provider_config::SourceDescription::HuggingFace(x) = &mut config.source.source {
+        x.operations.clear();
+        assert_eq!(x.get_split(), "train");
+    }
+    assert!(config.validate(&TaskType::Squad).is_ok());
+}